@@ -1,12 +1,31 @@
-#[derive(Default, Debug)]
+use crate::color::Color;
+use crate::geometry::{Rect, SizeRange};
+use crate::header::{Header, I18nConfig};
+use crate::shortcut::Shortcut;
+use crate::span::Span;
+use crate::style::{Align, BoxType, Font, LabelType, When};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+
+#[derive(Default, Debug, Clone)]
 pub struct ParentProps {
+    /// Row and column of the cell in an `Fl_Grid`
     pub location: Option<String>,
+    pub rowspan: Option<i32>,
+    pub colspan: Option<i32>,
+    pub align: Option<i32>,
+    /// Minimum width and height of the cell
+    pub min_size: Option<String>,
+    pub spans: BTreeMap<String, Span>,
 }
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone)]
 pub struct WidgetProps {
     pub open: Option<bool>,
     pub xywh: String,
+    /// Parsed `xywh`
+    pub rect: Option<Rect>,
     pub label: Option<String>,
     pub typ: Option<String>,
     pub hide: Option<bool>,
@@ -17,34 +36,36 @@ pub struct WidgetProps {
     pub noborder: Option<bool>,
     pub xclass: Option<String>,
     pub size_range: Option<String>,
+    /// Parsed `size_range`
+    pub size_limits: Option<SizeRange>,
     pub resizable: Option<bool>,
     pub hotspot: Option<bool>,
     pub divider: Option<bool>,
     pub selected: Option<bool>,
-    pub color: Option<u32>,
-    pub selection_color: Option<u32>,
+    pub color: Option<Color>,
+    pub selection_color: Option<Color>,
     pub tooltip: Option<String>,
     pub image: Option<String>,
     pub deimage: Option<String>,
-    pub r#box: Option<String>,
-    pub down_box: Option<String>,
+    pub r#box: Option<BoxType>,
+    pub down_box: Option<BoxType>,
     pub value: Option<String>,
-    pub labeltype: Option<String>,
-    pub labelfont: Option<i32>,
+    pub labeltype: Option<LabelType>,
+    pub labelfont: Option<Font>,
     pub labelsize: Option<i32>,
-    pub labelcolor: Option<u32>,
-    pub align: Option<i32>,
-    pub when: Option<i32>,
+    pub labelcolor: Option<Color>,
+    pub align: Option<Align>,
+    pub when: Option<When>,
     pub minimum: Option<f64>,
     pub maximum: Option<f64>,
     pub step: Option<f64>,
     pub slider_size: Option<f64>,
     pub size: Option<f64>,
-    pub textfont: Option<i32>,
+    pub textfont: Option<Font>,
     pub textsize: Option<i32>,
-    pub textcolor: Option<u32>,
+    pub textcolor: Option<Color>,
     pub class: Option<String>,
-    pub shortcut: Option<String>,
+    pub shortcut: Option<Shortcut>,
     pub code0: Option<String>,
     pub code1: Option<String>,
     pub code2: Option<String>,
@@ -59,92 +80,513 @@ pub struct WidgetProps {
     pub comment: Option<String>,
     pub dimensions: Option<String>,
     pub margin: Option<String>,
+    pub rowheights: Option<String>,
+    pub rowweights: Option<String>,
+    pub rowgaps: Option<String>,
+    pub colwidths: Option<String>,
+    pub colweights: Option<String>,
+    pub colgaps: Option<String>,
     pub parent_properties: Option<ParentProps>,
+    /// Spans of property values keyed by property name, braces included
+    pub spans: BTreeMap<String, Span>,
 }
 
-#[derive(Debug, Default)]
+#[derive(Default, Debug, PartialEq, Eq, Copy, Clone)]
+pub enum WidgetKind {
+    /// A widget type provided by FLTK
+    Builtin,
+    /// An instance of a `widget_class` defined in the file
+    WidgetClass,
+    #[default]
+    Unknown,
+}
+
+#[derive(Debug, Default, Clone)]
 pub struct Widget {
     pub typ: String,
+    pub kind: WidgetKind,
     pub name: String,
+    pub name_span: Span,
     pub props: WidgetProps,
     pub children: Vec<Widget>,
+    pub span: Span,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct WidgetClassProps {
+    /// Base class of the generated class, `Fl_Group` when unset
+    pub class: Option<String>,
+    /// Whether children are positioned relative to the widget
+    pub position_relative: Option<bool>,
+    /// Whether children are also scaled with the widget, which implies `position_relative`
+    pub position_relative_rescale: Option<bool>,
+    pub visibility: Option<Visibility>,
+    pub comment: Option<String>,
+    pub spans: BTreeMap<String, Span>,
+}
+
+/// A reusable widget defined as its own class
+#[derive(Debug, Default, Clone)]
+pub struct WidgetClass {
+    pub name: String,
+    pub name_span: Span,
+    pub props: WidgetClassProps,
+    /// Properties of the widget itself, like `xywh` and `size_range`
+    pub widget_props: WidgetProps,
+    pub children: Vec<Widget>,
+    pub span: Span,
 }
 
-#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[derive(Default, Debug, PartialEq, Eq, Copy, Clone)]
 pub enum Visibility {
+    #[default]
     PUBLIC,
     PRIVATE,
     PROTECTED,
 }
 
-impl Default for Visibility {
-    fn default() -> Self {
-        Visibility::PUBLIC
-    }
-}
-
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub struct FunctionProps {
     pub visibility: Option<Visibility>,
     pub open: Option<bool>,
     pub c: Option<bool>,
     pub return_type: Option<String>,
     pub comment: Option<String>,
+    pub spans: BTreeMap<String, Span>,
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub struct Function {
     pub name: String,
+    pub name_span: Span,
     pub props: FunctionProps,
-    pub code: Option<String>,
-    pub widgets: Vec<Widget>,
+    /// Code, widgets and other nodes of the body in source order
+    pub children: Vec<Item>,
+    pub span: Span,
+}
+
+impl Function {
+    pub fn code(&self) -> impl Iterator<Item = &Code> {
+        self.children.iter().filter_map(|i| match i {
+            Item::Code(c) => Some(c),
+            _ => None,
+        })
+    }
+    pub fn widgets(&self) -> impl Iterator<Item = &Widget> {
+        self.children.iter().filter_map(|i| match i {
+            Item::Widget(w) => Some(&**w),
+            _ => None,
+        })
+    }
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
+pub struct Code {
+    pub code: String,
+    pub code_span: Span,
+    pub span: Span,
+}
+
+#[derive(Debug, Default, Clone)]
 pub struct ClassProps {
     pub visibility: Option<Visibility>,
     pub open: Option<bool>,
     pub comment: Option<String>,
+    /// Raw base clause, like `public Fl_Double_Window`
+    pub subclass_of: Option<String>,
+    pub spans: BTreeMap<String, Span>,
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct BaseClass {
+    pub name: String,
+    /// Access specifier, C++ defaults to private when unset
+    pub access: Option<Visibility>,
+    pub is_virtual: bool,
+}
+
+#[derive(Debug, Default, Clone)]
 pub struct Class {
+    /// Word preceding the name, like `FL_EXPORT`
+    pub prefix: Option<String>,
     pub name: String,
+    /// Span of the name as written, which may include the base clause
+    pub name_span: Span,
+    pub bases: Vec<BaseClass>,
     pub props: ClassProps,
-    pub functions: Vec<Function>,
+    /// Class members in source order
+    pub members: Vec<Item>,
+    pub span: Span,
+}
+
+impl BaseClass {
+    /// Parses a base clause like `public A, virtual protected B<int, 2>`
+    pub fn parse_list(s: &str) -> Vec<BaseClass> {
+        let s = s.trim().trim_start_matches(':');
+        let mut bases = vec![];
+        let mut depth = 0;
+        let mut start = 0;
+        for (i, c) in s.char_indices() {
+            match c {
+                '<' | '(' => depth += 1,
+                '>' | ')' => depth -= 1,
+                ',' if depth == 0 => {
+                    bases.extend(Self::parse(&s[start..i]));
+                    start = i + 1;
+                }
+                _ => (),
+            }
+        }
+        bases.extend(Self::parse(&s[start..]));
+        bases
+    }
+    fn parse(s: &str) -> Option<BaseClass> {
+        let mut b = BaseClass::default();
+        let mut rest = s.trim();
+        loop {
+            let (word, tail) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+            match word {
+                "public" => b.access = Some(Visibility::PUBLIC),
+                "protected" => b.access = Some(Visibility::PROTECTED),
+                "private" => b.access = Some(Visibility::PRIVATE),
+                "virtual" => b.is_virtual = true,
+                _ => break,
+            }
+            rest = tail.trim_start();
+        }
+        if rest.is_empty() {
+            return None;
+        }
+        b.name = rest.to_string();
+        Some(b)
+    }
 }
 
-#[derive(Debug, Default)]
+impl Class {
+    pub fn classes(&self) -> impl Iterator<Item = &Class> {
+        self.members.iter().filter_map(|i| match i {
+            Item::Class(c) => Some(c),
+            _ => None,
+        })
+    }
+    pub fn functions(&self) -> impl Iterator<Item = &Function> {
+        self.members.iter().filter_map(|i| match i {
+            Item::Function(f) => Some(f),
+            _ => None,
+        })
+    }
+    pub fn decls(&self) -> impl Iterator<Item = &Decl> {
+        self.members.iter().filter_map(|i| match i {
+            Item::Decl(d) => Some(d),
+            _ => None,
+        })
+    }
+    pub fn comments(&self) -> impl Iterator<Item = &Comment> {
+        self.members.iter().filter_map(|i| match i {
+            Item::Comment(c) => Some(c),
+            _ => None,
+        })
+    }
+    pub fn widget_classes(&self) -> impl Iterator<Item = &WidgetClass> {
+        self.members.iter().filter_map(|i| match i {
+            Item::WidgetClass(w) => Some(&**w),
+            _ => None,
+        })
+    }
+}
+
+#[derive(Debug, Default, Clone)]
 pub struct DeclProps {
     pub visibility: Visibility,
     pub global: Option<bool>,
     pub local: Option<bool>,
+    pub spans: BTreeMap<String, Span>,
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub struct Decl {
     pub decl: String,
+    pub decl_span: Span,
     pub props: DeclProps,
+    pub span: Span,
+}
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum DataMode {
+    #[default]
+    Binary,
+    Text,
+    /// Binary data compressed with zlib when generating code
+    Compressed,
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
+pub struct DataProps {
+    pub visibility: Option<Visibility>,
+    pub local: Option<bool>,
+    pub global: Option<bool>,
+    pub filename: Option<String>,
+    pub mode: DataMode,
+    pub comment: Option<String>,
+    pub spans: BTreeMap<String, Span>,
+}
+
+/// A file embedded into the generated code as a variable
+#[derive(Debug, Default, Clone)]
+pub struct Data {
+    pub name: String,
+    pub name_span: Span,
+    pub props: DataProps,
+    pub span: Span,
+}
+
+impl Data {
+    /// Resolves the embedded file relative to the directory of the .fl file at `fl_path`
+    pub fn resolve_path(&self, fl_path: &Path) -> Option<PathBuf> {
+        let filename = self.props.filename.as_ref()?;
+        let dir = fl_path.parent().unwrap_or_else(|| Path::new(""));
+        Some(dir.join(filename))
+    }
+
+    /// Reads the embedded file, see `resolve_path`
+    pub fn load(&self, fl_path: &Path) -> io::Result<Vec<u8>> {
+        match self.resolve_path(fl_path) {
+            Some(path) => fs::read(path),
+            None => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("data `{}` has no filename", self.name),
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct CodeBlockProps {
+    pub open: Option<bool>,
+    /// Code closing the block, `}` when unset
+    pub after: Option<String>,
+    pub spans: BTreeMap<String, Span>,
+}
+
+/// A block of code, like `if (x)`, wrapping the code and widgets within it
+#[derive(Debug, Default, Clone)]
+pub struct CodeBlock {
+    pub code: String,
+    pub code_span: Span,
+    pub props: CodeBlockProps,
+    pub children: Vec<Item>,
+    pub span: Span,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct DeclBlockProps {
+    pub visibility: Option<Visibility>,
+    pub open: Option<bool>,
+    pub in_source: Option<bool>,
+    pub in_header: Option<bool>,
+    pub after: Option<String>,
+    pub spans: BTreeMap<String, Span>,
+}
+
+/// A declaration, like `#ifdef FOO`, wrapping the nodes within it
+#[derive(Debug, Default, Clone)]
+pub struct DeclBlock {
+    pub decl: String,
+    pub decl_span: Span,
+    pub props: DeclBlockProps,
+    pub children: Vec<Item>,
+    pub span: Span,
+}
+
+#[derive(Debug, Default, Clone)]
 pub struct CommentProps {
     pub in_source: Option<bool>,
     pub in_header: Option<bool>,
+    pub spans: BTreeMap<String, Span>,
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub struct Comment {
     pub comment: String,
+    pub comment_span: Span,
     pub props: CommentProps,
+    pub span: Span,
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub struct Ast {
-    pub i18n_type: Option<bool>,
+    pub header: Header,
+    pub i18n: I18nConfig,
+    /// Top-level items in source order
+    pub items: Vec<Item>,
+    #[deprecated(since = "0.2.0", note = "use `Ast::classes()` or `Ast::items`")]
     pub classes: Vec<Class>,
-    pub widget_classes: Vec<Widget>,
+    #[deprecated(since = "0.2.0", note = "use `Ast::widget_classes()` or `Ast::items`")]
+    pub widget_classes: Vec<WidgetClass>,
+    #[deprecated(since = "0.2.0", note = "use `Ast::functions()` or `Ast::items`")]
     pub functions: Vec<Function>,
+    #[deprecated(since = "0.2.0", note = "use `Ast::comments()` or `Ast::items`")]
     pub comments: Vec<Comment>,
+    #[deprecated(since = "0.2.0", note = "use `Ast::decls()` or `Ast::items`")]
     pub decls: Vec<Decl>,
 }
+
+/// A node of the document tree. Which kinds may appear depends on the parent,
+/// e.g. `Code` and `Widget` only occur within function and code block bodies.
+#[derive(Debug, Clone)]
+pub enum Item {
+    Class(Class),
+    Function(Function),
+    Decl(Decl),
+    Comment(Comment),
+    WidgetClass(Box<WidgetClass>),
+    Code(Code),
+    CodeBlock(CodeBlock),
+    DeclBlock(DeclBlock),
+    Data(Data),
+    Widget(Box<Widget>),
+}
+
+impl Item {
+    pub fn span(&self) -> Span {
+        match self {
+            Item::Class(c) => c.span,
+            Item::Function(f) => f.span,
+            Item::Decl(d) => d.span,
+            Item::Comment(c) => c.span,
+            Item::WidgetClass(w) => w.span,
+            Item::Code(c) => c.span,
+            Item::CodeBlock(c) => c.span,
+            Item::DeclBlock(d) => d.span,
+            Item::Data(d) => d.span,
+            Item::Widget(w) => w.span,
+        }
+    }
+}
+
+impl Ast {
+    pub fn classes(&self) -> impl Iterator<Item = &Class> {
+        self.items.iter().filter_map(|i| match i {
+            Item::Class(c) => Some(c),
+            _ => None,
+        })
+    }
+    pub fn functions(&self) -> impl Iterator<Item = &Function> {
+        self.items.iter().filter_map(|i| match i {
+            Item::Function(f) => Some(f),
+            _ => None,
+        })
+    }
+    pub fn decls(&self) -> impl Iterator<Item = &Decl> {
+        self.items.iter().filter_map(|i| match i {
+            Item::Decl(d) => Some(d),
+            _ => None,
+        })
+    }
+    pub fn comments(&self) -> impl Iterator<Item = &Comment> {
+        self.items.iter().filter_map(|i| match i {
+            Item::Comment(c) => Some(c),
+            _ => None,
+        })
+    }
+    pub fn widget_classes(&self) -> impl Iterator<Item = &WidgetClass> {
+        self.items.iter().filter_map(|i| match i {
+            Item::WidgetClass(w) => Some(&**w),
+            _ => None,
+        })
+    }
+    /// Fills the deprecated per-kind vectors with copies of `items`
+    #[allow(deprecated)]
+    pub(crate) fn fill_kind_vecs(&mut self) {
+        self.classes = self.classes().cloned().collect();
+        self.widget_classes = self.widget_classes().cloned().collect();
+        self.functions = self.functions().cloned().collect();
+        self.comments = self.comments().cloned().collect();
+        self.decls = self.decls().cloned().collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_ok;
+
+    fn data_fixture() -> (Ast, PathBuf) {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("fl_tests/data.fl");
+        (parse_ok(&fs::read_to_string(&path).unwrap()), path)
+    }
+
+    #[test]
+    fn data_props() {
+        let (a, _) = data_fixture();
+        let d = match &a.items[0] {
+            Item::Data(d) => d,
+            i => panic!("unexpected item {:?}", i),
+        };
+        assert_eq!(d.name, "license_text");
+        assert_eq!(d.props.mode, DataMode::Text);
+        assert_eq!(d.props.visibility, Some(Visibility::PRIVATE));
+        assert_eq!(d.props.local, Some(true));
+        match &a.classes().next().unwrap().members[..] {
+            [Item::Data(logo)] => {
+                assert_eq!(logo.props.mode, DataMode::Compressed);
+                assert_eq!(logo.props.filename.as_deref(), Some("logo.png"));
+            }
+            m => panic!("unexpected members {:?}", m),
+        };
+    }
+
+    #[test]
+    fn data_paths_are_relative_to_the_fl_file() {
+        let (a, path) = data_fixture();
+        let d = match &a.items[0] {
+            Item::Data(d) => d,
+            i => panic!("unexpected item {:?}", i),
+        };
+        let dir = path.parent().unwrap();
+        assert_eq!(d.resolve_path(&path), Some(dir.join("../LICENSE")));
+        let license = Path::new(env!("CARGO_MANIFEST_DIR")).join("LICENSE");
+        assert_eq!(d.load(&path).unwrap(), fs::read(license).unwrap());
+        // a bare file name resolves against the working directory
+        assert_eq!(
+            d.resolve_path(Path::new("data.fl")),
+            Some(PathBuf::from("../LICENSE"))
+        );
+    }
+
+    #[test]
+    fn data_load_errors() {
+        let (a, path) = data_fixture();
+        let logo = match &a.classes().next().unwrap().members[..] {
+            [Item::Data(d)] => d,
+            m => panic!("unexpected members {:?}", m),
+        };
+        assert_eq!(
+            logo.load(&path).unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+        let unnamed = Data {
+            name: "blob".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(unnamed.resolve_path(&path), None);
+        let e = unnamed.load(&path).unwrap_err();
+        assert_eq!(e.to_string(), "data `blob` has no filename");
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn deprecated_kind_vecs() {
+        let a = parse_ok(include_str!("../fl_tests/wc_instance.fl"));
+        let names = |v: &[Function]| v.iter().map(|f| f.name.clone()).collect::<Vec<_>>();
+        assert_eq!(names(&a.functions), ["make_window()"]);
+        assert_eq!(a.widget_classes.len(), 1);
+        assert_eq!(a.widget_classes[0].name, "MyPanel");
+        assert_eq!(a.classes.len(), 1);
+        assert_eq!(a.classes[0].name, "Dialogs");
+        let a = parse_ok(include_str!("../fl_tests/escapes.fl"));
+        assert_eq!(a.decls.len(), a.decls().count());
+        assert_eq!(a.decls[0].decl, "#include <stdio.h>");
+        assert!(a.comments.is_empty());
+    }
+}
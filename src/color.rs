@@ -0,0 +1,266 @@
+use crate::error::ValueError;
+use std::fmt;
+use std::str::FromStr;
+
+/// An FLTK color, either an index into the colormap or an RGB value.
+/// FLTK packs RGB colors as `0xRRGGBB00`, anything below 256 is an index.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Color {
+    Indexed(u8),
+    Rgb(u8, u8, u8),
+}
+
+impl Default for Color {
+    fn default() -> Self {
+        Color::FOREGROUND
+    }
+}
+
+impl Color {
+    pub const FOREGROUND: Color = Color::Indexed(0);
+    pub const BACKGROUND2: Color = Color::Indexed(7);
+    pub const INACTIVE: Color = Color::Indexed(8);
+    pub const SELECTION: Color = Color::Indexed(15);
+    pub const GRAY0: Color = Color::Indexed(32);
+    pub const DARK3: Color = Color::Indexed(39);
+    pub const DARK2: Color = Color::Indexed(45);
+    pub const DARK1: Color = Color::Indexed(47);
+    pub const BACKGROUND: Color = Color::Indexed(49);
+    pub const LIGHT1: Color = Color::Indexed(50);
+    pub const LIGHT2: Color = Color::Indexed(52);
+    pub const LIGHT3: Color = Color::Indexed(54);
+    pub const BLACK: Color = Color::Indexed(56);
+    pub const RED: Color = Color::Indexed(88);
+    pub const GREEN: Color = Color::Indexed(63);
+    pub const YELLOW: Color = Color::Indexed(95);
+    pub const BLUE: Color = Color::Indexed(216);
+    pub const MAGENTA: Color = Color::Indexed(248);
+    pub const CYAN: Color = Color::Indexed(223);
+    pub const DARK_RED: Color = Color::Indexed(72);
+    pub const DARK_GREEN: Color = Color::Indexed(60);
+    pub const DARK_YELLOW: Color = Color::Indexed(76);
+    pub const DARK_BLUE: Color = Color::Indexed(136);
+    pub const DARK_MAGENTA: Color = Color::Indexed(152);
+    pub const DARK_CYAN: Color = Color::Indexed(140);
+    pub const WHITE: Color = Color::Indexed(255);
+
+    /// Decodes FLTK's `Fl_Color` value
+    pub fn from_fltk(v: u32) -> Color {
+        if v & 0xffff_ff00 != 0 {
+            let [r, g, b, _] = v.to_be_bytes();
+            Color::Rgb(r, g, b)
+        } else {
+            Color::Indexed(v as u8)
+        }
+    }
+
+    /// Encodes the color as FLTK's `Fl_Color` value
+    pub fn to_fltk(self) -> u32 {
+        match self {
+            Color::Indexed(i) => i as u32,
+            Color::Rgb(r, g, b) => u32::from_be_bytes([r, g, b, 0]),
+        }
+    }
+
+    /// RGB components, looking indices up in the default colormap
+    pub fn to_rgb(self) -> (u8, u8, u8) {
+        match self {
+            Color::Indexed(i) => {
+                let [r, g, b, _] = COLORMAP[i as usize].to_be_bytes();
+                (r, g, b)
+            }
+            Color::Rgb(r, g, b) => (r, g, b),
+        }
+    }
+
+    /// The color as `0xRRGGBB`
+    pub fn to_hex(self) -> u32 {
+        let (r, g, b) = self.to_rgb();
+        u32::from_be_bytes([0, r, g, b])
+    }
+
+    /// Name of the `Fl_Color` constant, like `FL_RED`
+    pub fn name(self) -> Option<&'static str> {
+        NAMES.iter().find(|(_, c)| *c == self).map(|(n, _)| *n)
+    }
+
+    pub fn from_name(name: &str) -> Option<Color> {
+        NAMES.iter().find(|(n, _)| *n == name).map(|(_, c)| *c)
+    }
+}
+
+/// Names of the `Fl_Color` constants. `FL_GRAY` aliases `FL_BACKGROUND_COLOR`.
+const NAMES: &[(&str, Color)] = &[
+    ("FL_FOREGROUND_COLOR", Color::FOREGROUND),
+    ("FL_BACKGROUND2_COLOR", Color::BACKGROUND2),
+    ("FL_INACTIVE_COLOR", Color::INACTIVE),
+    ("FL_SELECTION_COLOR", Color::SELECTION),
+    ("FL_GRAY0", Color::GRAY0),
+    ("FL_DARK3", Color::DARK3),
+    ("FL_DARK2", Color::DARK2),
+    ("FL_DARK1", Color::DARK1),
+    ("FL_BACKGROUND_COLOR", Color::BACKGROUND),
+    ("FL_GRAY", Color::BACKGROUND),
+    ("FL_LIGHT1", Color::LIGHT1),
+    ("FL_LIGHT2", Color::LIGHT2),
+    ("FL_LIGHT3", Color::LIGHT3),
+    ("FL_BLACK", Color::BLACK),
+    ("FL_RED", Color::RED),
+    ("FL_GREEN", Color::GREEN),
+    ("FL_YELLOW", Color::YELLOW),
+    ("FL_BLUE", Color::BLUE),
+    ("FL_MAGENTA", Color::MAGENTA),
+    ("FL_CYAN", Color::CYAN),
+    ("FL_DARK_RED", Color::DARK_RED),
+    ("FL_DARK_GREEN", Color::DARK_GREEN),
+    ("FL_DARK_YELLOW", Color::DARK_YELLOW),
+    ("FL_DARK_BLUE", Color::DARK_BLUE),
+    ("FL_DARK_MAGENTA", Color::DARK_MAGENTA),
+    ("FL_DARK_CYAN", Color::DARK_CYAN),
+    ("FL_WHITE", Color::WHITE),
+];
+
+/// Accepts the decimal or `0x` hexadecimal `Fl_Color` value, `#RRGGBB`
+/// or the name of an `Fl_Color` constant
+impl FromStr for Color {
+    type Err = ValueError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let v = if let Some(hex) = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+            u32::from_str_radix(hex, 16).ok()
+        } else if let Some(hex) = s.strip_prefix('#') {
+            match hex.len() {
+                6 => u32::from_str_radix(hex, 16).ok().map(|v| v << 8),
+                _ => None,
+            }
+        } else if let Some(c) = Color::from_name(s) {
+            return Ok(c);
+        } else {
+            // FLUID writes `Fl_Color` with `%d`, so RGB colors may be negative
+            match s.parse::<i64>() {
+                Ok(v) if v < 0 && v >= i32::MIN as i64 => Some(v as i32 as u32),
+                Ok(v) if v >= 0 && v <= u32::MAX as i64 => Some(v as u32),
+                _ => None,
+            }
+        };
+        v.map(Color::from_fltk)
+            .ok_or_else(|| ValueError::new(format!("Invalid color `{}`", s)))
+    }
+}
+
+/// Writes the constant name when there is one, the index or `#RRGGBB` otherwise
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self.name(), self) {
+            (Some(name), _) => f.write_str(name),
+            (None, Color::Indexed(i)) => write!(f, "{}", i),
+            (None, Color::Rgb(r, g, b)) => write!(f, "#{:02x}{:02x}{:02x}", r, g, b),
+        }
+    }
+}
+
+/// FLTK's default colormap as `0xRRGGBB00`: the 16 basic colors, 16 tints
+/// used by FLUID's color chooser, the 24 entry gray ramp and the 5x8x5 color cube
+pub const COLORMAP: [u32; 256] = build_colormap();
+
+const fn build_colormap() -> [u32; 256] {
+    const BASE: [u32; 56] = [
+        0x00000000, 0xf4000000, 0x00f40000, 0xf4f40000, 0x0000f400, 0xf400f400, 0x00f4f400,
+        0xffffff00, 0x55555500, 0xc6717100, 0x71c67100, 0x8e8e3800, 0x7171c600, 0x8e388e00,
+        0x388e8e00, 0x00008000, 0xa8a89800, 0xe8e8d800, 0x68685800, 0x98a8a800, 0xd8e8e800,
+        0x58686800, 0x9c9ca800, 0xdcdce800, 0x5c5c6800, 0x9ca89c00, 0xdce8dc00, 0x5c685c00,
+        0x90909000, 0xc0c0c000, 0x50505000, 0xa0a0a000, 0x00000000, 0x0d0d0d00, 0x1a1a1a00,
+        0x26262600, 0x31313100, 0x3d3d3d00, 0x48484800, 0x55555500, 0x5f5f5f00, 0x6a6a6a00,
+        0x75757500, 0x80808000, 0x8a8a8a00, 0x95959500, 0xa0a0a000, 0xaaaaaa00, 0xb5b5b500,
+        0xc0c0c000, 0xcbcbcb00, 0xd5d5d500, 0xe0e0e000, 0xeaeaea00, 0xf5f5f500, 0xffffff00,
+    ];
+    let mut map = [0; 256];
+    let mut i = 0;
+    while i < 56 {
+        map[i] = BASE[i];
+        i += 1;
+    }
+    // index = 56 + (b * 5 + r) * 8 + g
+    while i < 256 {
+        let c = i as u32 - 56;
+        let g = (c % 8) * 255 / 7;
+        let r = (c / 8 % 5) * 255 / 4;
+        let b = (c / 40) * 255 / 4;
+        map[i] = (r << 24) | (g << 16) | (b << 8);
+        i += 1;
+    }
+    map
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_ok;
+
+    #[test]
+    fn index_and_rgb() {
+        assert_eq!(Color::from_fltk(88), Color::RED);
+        assert_eq!(Color::from_fltk(0xff000000), Color::Rgb(0xff, 0, 0));
+        assert_eq!(Color::from_fltk(0x12345600).to_fltk(), 0x12345600);
+        assert_eq!(Color::Indexed(255).to_fltk(), 255);
+        // the low byte of an RGB color is ignored
+        assert_eq!(Color::from_fltk(0x123456ff).to_fltk(), 0x12345600);
+    }
+
+    #[test]
+    fn colormap_lookup() {
+        assert_eq!(Color::BLACK.to_rgb(), (0, 0, 0));
+        assert_eq!(Color::WHITE.to_hex(), 0xffffff);
+        assert_eq!(Color::RED.to_hex(), 0xff0000);
+        assert_eq!(Color::BLUE.to_hex(), 0x0000ff);
+        assert_eq!(Color::BACKGROUND.to_hex(), 0xc0c0c0);
+        assert_eq!(Color::Indexed(1).to_hex(), 0xf40000);
+        assert_eq!(Color::Indexed(16).to_hex(), 0xa8a898);
+        // the color cube, 56 + (b * 5 + r) * 8 + g
+        assert_eq!(Color::Indexed(56 + 7).to_hex(), 0x00ff00);
+        assert_eq!(Color::Indexed(56 + 51).to_hex(), 0x3f6d3f);
+        assert_eq!(Color::Rgb(1, 2, 3).to_hex(), 0x010203);
+    }
+
+    #[test]
+    fn named_constants() {
+        assert_eq!(Color::RED.name(), Some("FL_RED"));
+        assert_eq!(Color::from_name("FL_GRAY"), Some(Color::BACKGROUND));
+        assert_eq!(Color::BACKGROUND.to_string(), "FL_BACKGROUND_COLOR");
+        assert_eq!(Color::Indexed(17).to_string(), "17");
+        assert_eq!(Color::Rgb(0x12, 0xab, 0).to_string(), "#12ab00");
+        assert_eq!(Color::from_name("FL_PURPLE"), None);
+    }
+
+    #[test]
+    fn parse_colors() {
+        let parse = |s: &str| s.parse::<Color>();
+        assert_eq!(parse("88"), Ok(Color::RED));
+        assert_eq!(parse("0xff000000"), Ok(Color::Rgb(0xff, 0, 0)));
+        assert_eq!(parse("0X0000FF00"), Ok(Color::Rgb(0, 0, 0xff)));
+        assert_eq!(parse("#12ab00"), Ok(Color::Rgb(0x12, 0xab, 0)));
+        assert_eq!(parse("FL_DARK_CYAN"), Ok(Color::DARK_CYAN));
+        assert_eq!(parse("4278190080"), Ok(Color::Rgb(0xff, 0, 0)));
+        assert_eq!(parse("-16776961"), Ok(Color::Rgb(0xff, 0, 0)));
+        assert_eq!(parse("-16776960"), Ok(Color::Rgb(0xff, 0, 1)));
+        assert_eq!(parse("-256"), Ok(Color::Rgb(0xff, 0xff, 0xff)));
+        for s in ["", "red", "#fff", "0xzz", "4294967296", "-2147483649", "1.5"] {
+            assert_eq!(
+                parse(s),
+                Err(ValueError::new(format!("Invalid color `{}`", s))),
+                "{}",
+                s
+            );
+        }
+        for c in [Color::RED, Color::Indexed(17), Color::Rgb(1, 2, 3)] {
+            assert_eq!(parse(&c.to_string()), Ok(c));
+        }
+    }
+
+    #[test]
+    fn negative_colors_in_files() {
+        let a = parse_ok("Function {} {} {\n  Fl_Box {} {\n    color -16776961 labelcolor 88\n  }\n}\n");
+        let w = a.functions().next().unwrap().widgets().next().unwrap();
+        assert_eq!(w.props.color, Some(Color::Rgb(0xff, 0, 0)));
+        assert_eq!(w.props.labelcolor, Some(Color::RED));
+    }
+}
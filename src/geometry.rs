@@ -0,0 +1,197 @@
+use crate::error::ValueError;
+use std::fmt;
+use std::str::FromStr;
+
+/// Position and size of a widget, from `xywh {x y w h}`
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub w: i32,
+    pub h: i32,
+}
+
+impl FromStr for Rect {
+    type Err = ValueError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let [x, y, w, h] = match parse_ints(s, "xywh")?[..] {
+            [x, y, w, h] => [x, y, w, h],
+            ref v => {
+                return Err(ValueError::new(format!(
+                    "Expected 4 values for `xywh`, found {}",
+                    v.len()
+                )))
+            }
+        };
+        Ok(Rect { x, y, w, h })
+    }
+}
+
+impl Rect {
+    /// Checks for a negative size, which FLUID loads as is
+    pub fn validate(&self) -> Result<(), ValueError> {
+        if self.w < 0 || self.h < 0 {
+            return Err(ValueError::new(format!(
+                "Negative size {}x{} in `xywh`",
+                self.w, self.h
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Rect {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {} {} {}", self.x, self.y, self.w, self.h)
+    }
+}
+
+/// Window size limits, from `size_range {min_w min_h max_w max_h}`,
+/// optionally followed by the resize increments and the aspect flag.
+/// A maximum of 0 means unlimited.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct SizeRange {
+    pub min_w: i32,
+    pub min_h: i32,
+    pub max_w: i32,
+    pub max_h: i32,
+    pub dw: i32,
+    pub dh: i32,
+    pub aspect: bool,
+}
+
+impl FromStr for SizeRange {
+    type Err = ValueError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let v = parse_ints(s, "size_range")?;
+        let r = match v[..] {
+            [min_w, min_h, max_w, max_h] => SizeRange {
+                min_w,
+                min_h,
+                max_w,
+                max_h,
+                ..Default::default()
+            },
+            [min_w, min_h, max_w, max_h, dw, dh, aspect] => SizeRange {
+                min_w,
+                min_h,
+                max_w,
+                max_h,
+                dw,
+                dh,
+                aspect: aspect != 0,
+            },
+            _ => {
+                return Err(ValueError::new(format!(
+                    "Expected 4 or 7 values for `size_range`, found {}",
+                    v.len()
+                )))
+            }
+        };
+        Ok(r)
+    }
+}
+
+impl SizeRange {
+    /// Checks for negative values and a maximum below the minimum, which FLUID loads as is
+    pub fn validate(&self) -> Result<(), ValueError> {
+        let r = self;
+        if r.min_w < 0 || r.min_h < 0 || r.max_w < 0 || r.max_h < 0 || r.dw < 0 || r.dh < 0 {
+            return Err(ValueError::new("Negative value in `size_range`"));
+        }
+        if (r.max_w != 0 && r.max_w < r.min_w) || (r.max_h != 0 && r.max_h < r.min_h) {
+            return Err(ValueError::new(format!(
+                "Maximum size {}x{} is below the minimum {}x{} in `size_range`",
+                r.max_w, r.max_h, r.min_w, r.min_h
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for SizeRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {}",
+            self.min_w, self.min_h, self.max_w, self.max_h
+        )?;
+        if self.dw != 0 || self.dh != 0 || self.aspect {
+            write!(f, " {} {} {}", self.dw, self.dh, self.aspect as i32)?;
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn parse_ints(s: &str, prop: &str) -> Result<Vec<i32>, ValueError> {
+    s.split_whitespace()
+        .map(|v| {
+            v.parse().map_err(|_| {
+                ValueError::new(format!("Invalid number `{}` in `{}`", v, prop))
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Severity;
+    use crate::lexer::Lexer;
+    use crate::parser::{parse_ok, Parser};
+
+    #[test]
+    fn parse_rect() {
+        let r: Rect = " 10 -20 30 40 ".parse().unwrap();
+        assert_eq!(r, Rect { x: 10, y: -20, w: 30, h: 40 });
+        assert_eq!(r.to_string(), "10 -20 30 40");
+        assert_eq!(r.validate(), Ok(()));
+        assert!("1 2 3".parse::<Rect>().is_err());
+        assert!("1 2 3 x".parse::<Rect>().is_err());
+        let r: Rect = "0 0 -5 10".parse().unwrap();
+        assert_eq!(r.w, -5);
+        assert_eq!(
+            r.validate().unwrap_err().message,
+            "Negative size -5x10 in `xywh`"
+        );
+    }
+
+    #[test]
+    fn parse_size_range() {
+        let r: SizeRange = "100 50 0 0".parse().unwrap();
+        assert_eq!((r.min_w, r.max_w, r.dw, r.aspect), (100, 0, 0, false));
+        assert_eq!(r.validate(), Ok(()));
+        let r: SizeRange = "10 10 800 600 5 5 1".parse().unwrap();
+        assert_eq!((r.dw, r.dh, r.aspect), (5, 5, true));
+        assert_eq!(r.to_string(), "10 10 800 600 5 5 1");
+        assert!("1 2 3 4 5".parse::<SizeRange>().is_err());
+        let r: SizeRange = "200 200 100 300".parse().unwrap();
+        assert_eq!(
+            r.validate().unwrap_err().message,
+            "Maximum size 100x300 is below the minimum 200x200 in `size_range`"
+        );
+        let r: SizeRange = "-1 0 0 0".parse().unwrap();
+        assert!(r.validate().is_err());
+    }
+
+    #[test]
+    fn invalid_geometry_is_kept_with_warnings() {
+        let s = "Function {} {} {\n  Fl_Window {} {\n    xywh {0 0 -10 20} size_range {300 200 100 100}\n  } {}\n}\n";
+        let a = parse_ok(s);
+        let w = a.functions().next().unwrap().widgets().next().unwrap();
+        assert_eq!(w.props.rect.map(|r| r.w), Some(-10));
+        assert_eq!(w.props.size_limits.map(|r| r.max_w), Some(100));
+        let (_, d) = Parser::new(Lexer::new(s)).parse_with_diagnostics();
+        let d: Vec<_> = d
+            .iter()
+            .map(|d| (d.severity, &s[d.span.start..d.span.end]))
+            .collect();
+        assert_eq!(
+            d,
+            [
+                (Severity::Warning, "{0 0 -10 20}"),
+                (Severity::Warning, "{300 200 100 100}"),
+            ]
+        );
+    }
+}
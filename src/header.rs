@@ -0,0 +1,239 @@
+use crate::error::ValueError;
+use std::fmt;
+use std::str::FromStr;
+
+/// FLUID file format version, written as `major.MMPP`, e.g. `1.0400` for 1.4.0
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Version {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl FromStr for Version {
+    type Err = ValueError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ValueError::new(format!("Invalid version `{}`", s));
+        let (major, fraction) = match s.find('.') {
+            Some(i) => (&s[..i], &s[i + 1..]),
+            None => (s, ""),
+        };
+        let is_number = |d: &str| d.bytes().all(|b| b.is_ascii_digit());
+        if major.is_empty() || !is_number(major) || !is_number(fraction) || fraction.len() > 4 {
+            return Err(invalid());
+        }
+        let minor = fraction.get(..2).unwrap_or(fraction);
+        let patch = fraction.get(2..).unwrap_or("");
+        // a trailing single digit is a tens digit, as in `1.4` -> 1.40.0 or `1.040` -> 1.4.0
+        let digits = |d: &str| -> Result<u32, ValueError> {
+            match d.len() {
+                0 => Ok(0),
+                1 => Ok(d.parse::<u32>().map_err(|_| invalid())? * 10),
+                _ => d.parse().map_err(|_| invalid()),
+            }
+        };
+        Ok(Version {
+            major: major.parse().map_err(|_| invalid())?,
+            minor: digits(minor)?,
+            patch: digits(patch)?,
+        })
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{:02}{:02}", self.major, self.minor, self.patch)
+    }
+}
+
+/// Project settings stored at the top of a .fl file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Header {
+    pub version: Option<Version>,
+    /// Extension or file name of the generated header, e.g. `.h`
+    pub header_name: Option<String>,
+    /// Extension or file name of the generated source, e.g. `.cxx`
+    pub code_name: Option<String>,
+    pub avoid_early_includes: bool,
+    pub include_h_from_c: bool,
+    pub use_fl_command: bool,
+    pub utf8_in_src: bool,
+    pub format_code: bool,
+}
+
+impl Default for Header {
+    fn default() -> Self {
+        Header {
+            version: None,
+            header_name: None,
+            code_name: None,
+            avoid_early_includes: false,
+            include_h_from_c: true,
+            use_fl_command: false,
+            utf8_in_src: false,
+            format_code: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GettextConfig {
+    pub include: String,
+    pub conditional: String,
+    pub function: String,
+    pub static_function: String,
+}
+
+impl Default for GettextConfig {
+    fn default() -> Self {
+        GettextConfig {
+            include: "<libintl.h>".to_string(),
+            conditional: String::new(),
+            function: "gettext".to_string(),
+            static_function: "gettext_noop".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CatgetsConfig {
+    pub include: String,
+    pub conditional: String,
+    /// Name of the message catalog, defaults to the generated file's name
+    pub file: String,
+    pub set: String,
+}
+
+impl Default for CatgetsConfig {
+    fn default() -> Self {
+        CatgetsConfig {
+            include: "<nl_types.h>".to_string(),
+            conditional: String::new(),
+            file: String::new(),
+            set: "1".to_string(),
+        }
+    }
+}
+
+/// Internationalization settings, selected by `i18n_type`
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub enum I18nConfig {
+    #[default]
+    None,
+    /// GNU gettext, `i18n_type 1`
+    Gettext(GettextConfig),
+    /// POSIX catgets, `i18n_type 2`
+    Catgets(CatgetsConfig),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Severity;
+    use crate::lexer::Lexer;
+    use crate::parser::{parse_ok, Parser};
+
+    fn version(major: u32, minor: u32, patch: u32) -> Version {
+        Version {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    #[test]
+    fn parse_versions() {
+        assert_eq!("1.0400".parse(), Ok(version(1, 4, 0)));
+        assert_eq!("1.0308".parse(), Ok(version(1, 3, 8)));
+        assert_eq!("1.0310".parse(), Ok(version(1, 3, 10)));
+        assert_eq!("1.4".parse(), Ok(version(1, 40, 0)));
+        assert_eq!("1.040".parse(), Ok(version(1, 4, 0)));
+        assert_eq!("1".parse(), Ok(version(1, 0, 0)));
+        assert_eq!("2.".parse(), Ok(version(2, 0, 0)));
+        assert!(version(1, 3, 8) < version(1, 4, 0));
+    }
+
+    #[test]
+    fn version_round_trip() {
+        for s in ["1.0400", "1.0308", "1.0310", "0.0000"] {
+            assert_eq!(s.parse::<Version>().unwrap().to_string(), s);
+        }
+    }
+
+    #[test]
+    fn malformed_versions() {
+        for s in [
+            "", ".04", "x.0400", "1.04a0", "1.a", "1.+4", "-1.0400", "1.04001", "1.0400.1",
+        ] {
+            assert!(s.parse::<Version>().is_err(), "{:?}", s);
+        }
+        assert_eq!(
+            "1.04x0".parse::<Version>().unwrap_err().message,
+            "Invalid version `1.04x0`"
+        );
+    }
+
+    #[test]
+    fn header_version() {
+        let a = parse_ok(include_str!("../fl_tests/menu.fl"));
+        assert_eq!(a.header.version, Some(version(1, 4, 0)));
+        assert!(Parser::new(Lexer::new("version 1.04x0\n"))
+            .try_parse()
+            .is_err());
+    }
+
+    #[test]
+    fn gettext_config() {
+        let a = parse_ok(include_str!("../fl_tests/i18n.fl"));
+        assert_eq!(
+            a.i18n,
+            I18nConfig::Gettext(GettextConfig {
+                include: "<libintl.h>".to_string(),
+                conditional: "HAVE_GETTEXT".to_string(),
+                function: "_".to_string(),
+                static_function: "N_".to_string(),
+            })
+        );
+        assert_eq!(a.header.header_name.as_deref(), Some(".h"));
+        assert_eq!(parse_ok("version 1.0400\n").i18n, I18nConfig::None);
+    }
+
+    #[test]
+    fn catgets_config() {
+        let a = parse_ok("i18n_type 2\ni18n_include <nl_types.h>\ni18n_file catalog\n");
+        assert_eq!(
+            a.i18n,
+            I18nConfig::Catgets(CatgetsConfig {
+                file: "catalog".to_string(),
+                ..Default::default()
+            })
+        );
+        let a = parse_ok("i18n_type 2\ni18n_pos_file {}\ni18n_pos_set 3\n");
+        match a.i18n {
+            I18nConfig::Catgets(c) => assert_eq!((&*c.file, &*c.set), ("", "3")),
+            i => panic!("{:?}", i),
+        }
+    }
+
+    #[test]
+    fn i18n_problems() {
+        let s = "i18n_type 1\ni18n_file catalog\ni18n_type 3\n";
+        let (a, d) = Parser::new(Lexer::new(s)).parse_with_diagnostics();
+        assert_eq!(a.i18n, I18nConfig::Gettext(Default::default()));
+        let d: Vec<_> = d
+            .iter()
+            .map(|d| (d.severity, &s[d.span.start..d.span.end], &*d.message))
+            .collect();
+        assert_eq!(
+            d,
+            [
+                (
+                    Severity::Warning,
+                    "i18n_file",
+                    "Ignoring `i18n_file` for the selected i18n type"
+                ),
+                (Severity::Error, "3", "Unknown i18n type `3`"),
+            ]
+        );
+    }
+}
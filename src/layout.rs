@@ -0,0 +1,376 @@
+use crate::ast::{Widget, WidgetProps};
+use crate::error::ValueError;
+use crate::geometry::parse_ints;
+use crate::subtype::{decode_type, Subtype};
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum FlexDirection {
+    /// Children stacked top to bottom, FLTK's `Fl_Flex::COLUMN`
+    #[default]
+    Vertical,
+    /// Children placed left to right, FLTK's `Fl_Flex::ROW`
+    Horizontal,
+}
+
+/// Layout of an `Fl_Flex`, from its `type`, `margins`, `gap` and fixed size tuples
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct FlexLayout {
+    pub direction: FlexDirection,
+    /// Left, top, right and bottom margins
+    pub margins: [i32; 4],
+    pub gap: i32,
+    /// Children with a fixed size, as child index and size
+    pub fixed: Vec<(usize, i32)>,
+}
+
+impl Widget {
+    /// Layout of an `Fl_Flex`, `None` for other widgets
+    pub fn flex_layout(&self) -> Result<Option<FlexLayout>, ValueError> {
+        if self.typ != "Fl_Flex" {
+            return Ok(None);
+        }
+        FlexLayout::from_props(&self.props, self.children.len()).map(Some)
+    }
+}
+
+impl FlexLayout {
+    /// Builds the layout of a flex with `children` children, e.g. a widget class based on `Fl_Flex`
+    pub fn from_props(props: &WidgetProps, children: usize) -> Result<FlexLayout, ValueError> {
+        let direction = match &props.typ {
+            Some(typ) => match decode_type("Fl_Flex", typ)?.subtype {
+                Subtype::Horizontal => FlexDirection::Horizontal,
+                _ => FlexDirection::Vertical,
+            },
+            None => FlexDirection::Vertical,
+        };
+        let mut l = FlexLayout {
+            direction,
+            ..Default::default()
+        };
+        // FLUID has written both spellings
+        if let Some(margins) = props.margins.as_ref().or(props.margin.as_ref()) {
+            l.margins = match parse_ints(margins, "margins")?[..] {
+                [m] => [m; 4],
+                [left, top, right, bottom] => [left, top, right, bottom],
+                ref v => {
+                    return Err(ValueError::new(format!(
+                        "Expected 4 values for `margins`, found {}",
+                        v.len()
+                    )))
+                }
+            };
+        }
+        if let Some(gap) = &props.gap {
+            l.gap = match parse_ints(gap, "gap")?[..] {
+                [gap] => gap,
+                ref v => {
+                    return Err(ValueError::new(format!(
+                        "Expected 1 value for `gap`, found {}",
+                        v.len()
+                    )))
+                }
+            };
+        }
+        // FLUID ignores indices beyond the children when loading the file
+        for (index, size) in size_tuples(props)? {
+            if index >= 0 && (index as usize) < children {
+                l.fixed.push((index as usize, size));
+            }
+        }
+        Ok(l)
+    }
+
+    /// Fixed size indices which don't refer to one of the `children` children
+    pub(crate) fn stray_indices(props: &WidgetProps, children: usize) -> Vec<i32> {
+        size_tuples(props)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(index, _)| index)
+            .filter(|index| *index < 0 || *index as usize >= children)
+            .collect()
+    }
+
+    /// Fixed size of child `index`, if any
+    pub fn fixed_size(&self, index: usize) -> Option<i32> {
+        self.fixed.iter().find(|(i, _)| *i == index).map(|(_, s)| *s)
+    }
+}
+
+/// `FL_GRID_FILL`, the alignment of grid cells which don't set one
+pub const GRID_FILL: i32 = 0x30;
+
+/// Placement of a child in an `Fl_Grid`, from its `parent_properties`
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct GridCell {
+    /// Index of the child within the grid
+    pub child: usize,
+    pub row: i32,
+    pub col: i32,
+    pub rowspan: i32,
+    pub colspan: i32,
+    /// FLTK's `Fl_Grid_Align`
+    pub align: i32,
+    pub min_w: i32,
+    pub min_h: i32,
+}
+
+/// Layout of an `Fl_Grid`. Row and column sizes hold one entry per row or
+/// column, filled with FLTK's defaults where the file has none.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct GridLayout {
+    pub rows: i32,
+    pub cols: i32,
+    /// Left, top, right and bottom margins
+    pub margins: [i32; 4],
+    pub row_gap: i32,
+    pub col_gap: i32,
+    /// Fixed heights, 0 to size the row by its weight
+    pub row_heights: Vec<i32>,
+    pub row_weights: Vec<i32>,
+    /// Gaps below each row, -1 for the default `row_gap`
+    pub row_gaps: Vec<i32>,
+    pub col_widths: Vec<i32>,
+    pub col_weights: Vec<i32>,
+    pub col_gaps: Vec<i32>,
+    /// Cells of the children which are placed in the grid
+    pub cells: Vec<GridCell>,
+}
+
+impl Widget {
+    /// Layout of an `Fl_Grid`, `None` for other widgets
+    pub fn grid_layout(&self) -> Result<Option<GridLayout>, ValueError> {
+        if self.typ != "Fl_Grid" {
+            return Ok(None);
+        }
+        GridLayout::from_props(&self.props, &self.children).map(Some)
+    }
+}
+
+impl GridLayout {
+    /// Builds the layout of a grid from its props and its children's `parent_properties`
+    pub fn from_props(props: &WidgetProps, children: &[Widget]) -> Result<GridLayout, ValueError> {
+        let mut l = GridLayout::default();
+        if let Some(dimensions) = &props.dimensions {
+            let [rows, cols] = pair(dimensions, "dimensions")?;
+            if rows < 0 || cols < 0 {
+                return Err(ValueError::new(format!(
+                    "Negative grid dimensions {}x{}",
+                    rows, cols
+                )));
+            }
+            l.rows = rows;
+            l.cols = cols;
+        }
+        if let Some(margin) = &props.margin {
+            l.margins = match parse_ints(margin, "margin")?[..] {
+                [left, top, right, bottom] => [left, top, right, bottom],
+                ref v => {
+                    return Err(ValueError::new(format!(
+                        "Expected 4 values for `margin`, found {}",
+                        v.len()
+                    )))
+                }
+            };
+        }
+        if let Some(gap) = &props.gap {
+            let [row_gap, col_gap] = pair(gap, "gap")?;
+            l.row_gap = row_gap;
+            l.col_gap = col_gap;
+        }
+        let rows = l.rows as usize;
+        let cols = l.cols as usize;
+        l.row_heights = sizes(&props.rowheights, "rowheights", rows, 0)?;
+        l.row_weights = sizes(&props.rowweights, "rowweights", rows, 50)?;
+        l.row_gaps = sizes(&props.rowgaps, "rowgaps", rows, -1)?;
+        l.col_widths = sizes(&props.colwidths, "colwidths", cols, 0)?;
+        l.col_weights = sizes(&props.colweights, "colweights", cols, 50)?;
+        l.col_gaps = sizes(&props.colgaps, "colgaps", cols, -1)?;
+        for (child, w) in children.iter().enumerate() {
+            let p = match &w.props.parent_properties {
+                Some(p) => p,
+                None => continue,
+            };
+            let location = match &p.location {
+                Some(location) => location,
+                None => continue,
+            };
+            let [row, col] = pair(location, "location")?;
+            let mut cell = GridCell {
+                child,
+                row,
+                col,
+                rowspan: p.rowspan.unwrap_or(1),
+                colspan: p.colspan.unwrap_or(1),
+                align: p.align.unwrap_or(GRID_FILL),
+                ..Default::default()
+            };
+            if let Some(min_size) = &p.min_size {
+                let [min_w, min_h] = pair(min_size, "min_size")?;
+                cell.min_w = min_w;
+                cell.min_h = min_h;
+            }
+            if cell.rowspan < 1 || cell.colspan < 1 {
+                return Err(ValueError::new(format!(
+                    "Invalid span {}x{} of child {}",
+                    cell.rowspan, cell.colspan, child
+                )));
+            }
+            if row < 0 || col < 0 || row + cell.rowspan > l.rows || col + cell.colspan > l.cols {
+                return Err(ValueError::new(format!(
+                    "Cell at row {} and column {} spanning {}x{} of child {} is outside of the {}x{} grid",
+                    row, col, cell.rowspan, cell.colspan, child, l.rows, l.cols
+                )));
+            }
+            l.cells.push(cell);
+        }
+        Ok(l)
+    }
+
+    /// Cell of child `index`, if it is placed in the grid
+    pub fn cell(&self, index: usize) -> Option<&GridCell> {
+        self.cells.iter().find(|c| c.child == index)
+    }
+}
+
+/// Index and size pairs of `set_size_tuples`, preceded by their count
+fn size_tuples(props: &WidgetProps) -> Result<Vec<(i32, i32)>, ValueError> {
+    let tuples = match &props.size_tuple {
+        Some(tuples) => tuples,
+        None => return Ok(vec![]),
+    };
+    let v = parse_ints(tuples, "size tuples")?;
+    let (count, pairs) = v.split_first().map_or((0, &[][..]), |(c, p)| (*c, p));
+    if pairs.len() % 2 != 0 || count as usize != pairs.len() / 2 {
+        return Err(ValueError::new(format!(
+            "Expected {} index and size pairs in size tuples, found `{}`",
+            count,
+            tuples.trim()
+        )));
+    }
+    Ok(pairs.chunks(2).map(|p| (p[0], p[1])).collect())
+}
+
+fn pair(s: &str, prop: &str) -> Result<[i32; 2], ValueError> {
+    match parse_ints(s, prop)?[..] {
+        [a, b] => Ok([a, b]),
+        ref v => Err(ValueError::new(format!(
+            "Expected 2 values for `{}`, found {}",
+            prop,
+            v.len()
+        ))),
+    }
+}
+
+/// Per row or column values, `len` copies of `default` when unset
+fn sizes(s: &Option<String>, prop: &str, len: usize, default: i32) -> Result<Vec<i32>, ValueError> {
+    let s = match s {
+        Some(s) => s,
+        None => return Ok(vec![default; len]),
+    };
+    let v = parse_ints(s, prop)?;
+    if v.len() != len {
+        return Err(ValueError::new(format!(
+            "Expected {} values for `{}`, found {}",
+            len,
+            prop,
+            v.len()
+        )));
+    }
+    Ok(v)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Severity;
+    use crate::lexer::Lexer;
+    use crate::parser::{parse_ok, Parser};
+
+    fn grid(s: &str) -> GridLayout {
+        let a = parse_ok(s);
+        let win = a.functions().next().unwrap().widgets().next().unwrap();
+        win.children[0].grid_layout().unwrap().unwrap()
+    }
+
+    fn flexes(w: &Widget, out: &mut Vec<Result<Option<FlexLayout>, ValueError>>) {
+        if w.typ == "Fl_Flex" {
+            out.push(w.flex_layout());
+        }
+        for c in &w.children {
+            flexes(c, out);
+        }
+    }
+
+    #[test]
+    fn flex_fixture() {
+        let a = parse_ok(include_str!("../fl_tests/flex.fl"));
+        let f = a.classes().next().unwrap().functions().next().unwrap();
+        let mut v = vec![];
+        flexes(f.widgets().next().unwrap(), &mut v);
+        let v: Vec<_> = v.into_iter().map(|l| l.unwrap().unwrap()).collect();
+        // the outer flex has two children, FLUID drops the fixed size of child 2
+        assert_eq!(v[0].direction, FlexDirection::Vertical);
+        assert_eq!(v[0].gap, 5);
+        assert_eq!(v[0].fixed, [(0, 30)]);
+        assert_eq!(v[1].direction, FlexDirection::Horizontal);
+        assert_eq!(v[1].fixed, [(0, 80), (1, 80), (3, 30)]);
+        assert_eq!(v[1].fixed_size(3), Some(30));
+        assert_eq!(v[1].fixed_size(2), None);
+        assert_eq!(v[2].direction, FlexDirection::Horizontal);
+        assert!(v[2].fixed.is_empty());
+    }
+
+    #[test]
+    fn flex_stray_index_warning() {
+        let s = include_str!("../fl_tests/flex.fl");
+        let (_, d) = Parser::new(Lexer::new(s)).parse_with_diagnostics();
+        assert_eq!(d.len(), 1);
+        assert_eq!(d[0].severity, Severity::Warning);
+        assert_eq!(
+            d[0].message,
+            "Ignoring fixed size index 2 out of range for 2 children"
+        );
+        assert_eq!(&s[d[0].span.start..d[0].span.end], "{2  0 30  2 30 }");
+    }
+
+    #[test]
+    fn flex_errors() {
+        let props = |tuples: &str, margins: &str| WidgetProps {
+            size_tuple: Some(tuples.to_string()),
+            margins: Some(margins.to_string()),
+            ..Default::default()
+        };
+        let e = FlexLayout::from_props(&props("2 0 30", "0"), 2).unwrap_err();
+        assert_eq!(
+            e.message,
+            "Expected 2 index and size pairs in size tuples, found `2 0 30`"
+        );
+        let e = FlexLayout::from_props(&props("0", "1 2"), 2).unwrap_err();
+        assert_eq!(e.message, "Expected 4 values for `margins`, found 2");
+        let l = FlexLayout::from_props(&props("1 -1 5", "3"), 2).unwrap();
+        assert!(l.fixed.is_empty());
+        assert_eq!(l.margins, [3; 4]);
+    }
+
+    #[test]
+    fn grid_defaults() {
+        let l = grid(include_str!("../fl_tests/grid.fl"));
+        assert_eq!((l.rows, l.cols), (3, 3));
+        assert_eq!(l.margins, [1, 0, 0, 0]);
+        assert_eq!(l.row_weights, [50; 3]);
+        assert_eq!(l.col_gaps, [-1; 3]);
+        assert_eq!(l.cell(1).map(|c| (c.row, c.col, c.align)), Some((2, 2, GRID_FILL)));
+    }
+
+    #[test]
+    fn grid_sizing_and_spans() {
+        let l = grid(include_str!("../fl_tests/grid_sizing.fl"));
+        assert_eq!(l.row_heights, [0, 40, 0]);
+        assert_eq!(l.row_weights, [50, 0, 50]);
+        assert_eq!(l.col_widths, [0; 3]);
+        assert_eq!(l.col_gaps, [-1, 5, -1]);
+        let c = l.cell(2).unwrap();
+        assert_eq!((c.row, c.col, c.rowspan, c.colspan), (1, 0, 1, 3));
+        assert_eq!((c.align, c.min_w, c.min_h), (16, 100, 30));
+    }
+}
@@ -7,7 +7,7 @@ pub struct Lexer<'a> {
 }
 
 impl<'a> Lexer<'a> {
-    pub fn new(source: &str) -> Lexer {
+    pub fn new(source: &str) -> Lexer<'_> {
         let size = source.len();
         let mut cursor = 0;
         if source.starts_with("# ") {
@@ -64,14 +64,13 @@ impl<'a> Lexer<'a> {
                 t.typ = TokenType::Word;
 
                 t.start = self.cursor;
-                while self.cursor < self.size
-                    && self.s.as_bytes()[self.cursor] != b' '
-                    && self.s.as_bytes()[self.cursor] != b'\n'
-                    && self.s.as_bytes()[self.cursor] != b'\r'
-                    && self.s.as_bytes()[self.cursor] != b'\t'
-                    && self.s.as_bytes()[self.cursor] != b'}'
-                {
-                    self.cursor += 1;
+                while self.cursor < self.size {
+                    match self.s.as_bytes()[self.cursor] {
+                        b' ' | b'\n' | b'\r' | b'\t' | b'{' | b'}' => break,
+                        // an escaped character never ends the word
+                        b'\\' => self.cursor = (self.cursor + 2).min(self.size),
+                        _ => self.cursor += 1,
+                    }
                 }
                 t.end = self.cursor;
                 t.word = &self.s[t.start..t.end];
@@ -80,3 +79,145 @@ impl<'a> Lexer<'a> {
         }
     }
 }
+
+/// Resolves backslash escapes the way FLUID reads them: `\{`, `\}`, `\\` and
+/// `\#` yield the escaped character, C escapes like `\n`, `\x41` and `\101`
+/// are decoded, and a backslash before a newline joins the lines. Numeric
+/// escapes are bytes, so sequences like `\303\251` decode as UTF-8.
+pub fn unescape(raw: &str) -> String {
+    let mut s = Vec::with_capacity(raw.len());
+    let mut chars = raw.chars().peekable();
+    let push = |s: &mut Vec<u8>, c: char| {
+        s.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+    };
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            push(&mut s, c);
+            continue;
+        }
+        let c = match chars.next() {
+            Some(c) => c,
+            None => {
+                s.push(b'\\');
+                break;
+            }
+        };
+        match c {
+            '\n' => (),
+            'a' => s.push(0x07),
+            'b' => s.push(0x08),
+            'f' => s.push(0x0c),
+            'n' => s.push(b'\n'),
+            'r' => s.push(b'\r'),
+            't' => s.push(b'\t'),
+            'v' => s.push(0x0b),
+            'x' => {
+                let mut v = 0;
+                for _ in 0..2 {
+                    match chars.peek().and_then(|c| c.to_digit(16)) {
+                        Some(d) => v = v * 16 + d,
+                        None => break,
+                    }
+                    chars.next();
+                }
+                s.push(v as u8);
+            }
+            '0'..='7' => {
+                let mut v = c as u32 - '0' as u32;
+                for _ in 0..2 {
+                    match chars.peek().and_then(|c| c.to_digit(8)) {
+                        Some(d) => v = v * 8 + d,
+                        None => break,
+                    }
+                    chars.next();
+                }
+                s.push(v as u8);
+            }
+            c => push(&mut s, c),
+        }
+    }
+    String::from_utf8_lossy(&s).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(s: &str) -> Vec<(TokenType, &str)> {
+        let mut l = Lexer::new(s);
+        let mut v = vec![];
+        loop {
+            let t = l.next();
+            if t.typ == TokenType::Eof {
+                return v;
+            }
+            v.push((t.typ, &s[t.start..t.end]));
+        }
+    }
+
+    #[test]
+    fn escaped_braces_stay_in_words() {
+        assert_eq!(
+            words("{a \\} b}"),
+            vec![
+                (TokenType::OpenBrace, "{"),
+                (TokenType::Word, "a"),
+                (TokenType::Word, "\\}"),
+                (TokenType::Word, "b"),
+                (TokenType::CloseBrace, "}"),
+            ]
+        );
+        assert_eq!(words("x\\{y"), vec![(TokenType::Word, "x\\{y")]);
+    }
+
+    #[test]
+    fn escaped_backslash_and_hash() {
+        assert_eq!(
+            words("\\\\ \\#"),
+            vec![(TokenType::Word, "\\\\"), (TokenType::Word, "\\#")]
+        );
+        // the second backslash is escaped, so the brace closes the group
+        assert_eq!(
+            words("{\\\\}"),
+            vec![
+                (TokenType::OpenBrace, "{"),
+                (TokenType::Word, "\\\\"),
+                (TokenType::CloseBrace, "}"),
+            ]
+        );
+    }
+
+    #[test]
+    fn escaped_newline_joins_the_word() {
+        assert_eq!(
+            words("a\\\nb c"),
+            vec![(TokenType::Word, "a\\\nb"), (TokenType::Word, "c")]
+        );
+        assert_eq!(words("a\\"), vec![(TokenType::Word, "a\\")]);
+    }
+
+    #[test]
+    fn unescape_fluid_escapes() {
+        assert_eq!(unescape("\\{ \\} \\\\ \\#include"), "{ } \\ #include");
+        assert_eq!(unescape("a\\\nb"), "ab");
+        assert_eq!(unescape("plain"), "plain");
+    }
+
+    #[test]
+    fn unescape_c_escapes() {
+        assert_eq!(unescape("\\n\\t\\r\\a\\b\\f\\v"), "\n\t\r\x07\x08\x0c\x0b");
+        assert_eq!(unescape("\\x41\\x4a2"), "AJ2");
+        assert_eq!(unescape("\\101\\0"), "A\0");
+        assert_eq!(unescape("\\q"), "q");
+        assert_eq!(unescape("end\\"), "end\\");
+    }
+
+    #[test]
+    fn unescape_numeric_escapes_as_utf8_bytes() {
+        assert_eq!(unescape("caf\\303\\251"), "café");
+        assert_eq!(unescape("\\xe2\\x82\\xac 5"), "€ 5");
+        assert_eq!(unescape("é\\x41"), "éA");
+        // bytes which aren't UTF-8 are replaced
+        assert_eq!(unescape("\\351t\\xe9"), "\u{fffd}t\u{fffd}");
+    }
+}
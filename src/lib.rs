@@ -1,4 +1,17 @@
 pub mod ast;
+pub mod color;
+pub mod error;
+pub mod geometry;
+pub mod header;
+pub mod layout;
 pub mod lexer;
+pub mod menu;
 pub mod parser;
+pub mod shortcut;
+pub mod signature;
+pub mod span;
+pub mod style;
+pub mod subtype;
 pub mod token;
+pub mod widgets;
+pub mod window;
@@ -0,0 +1,274 @@
+use crate::ast::Widget;
+use crate::shortcut::Shortcut;
+
+/// Widgets whose children are menu items
+pub const MENU_WIDGETS: &[&str] = &[
+    "Fl_Menu_Bar",
+    "Fl_Sys_Menu_Bar",
+    "Fl_Menu_Button",
+    "Fl_Choice",
+    "Fl_Input_Choice",
+];
+
+/// Types of the nodes FLUID writes for menu items
+pub const MENU_ITEM_TYPES: &[&str] = &["MenuItem", "Submenu", "Checkbox", "RadioMenuItem"];
+
+/// Flags of FLTK's `Fl_Menu_Item`
+pub const FL_MENU_INACTIVE: i32 = 1;
+pub const FL_MENU_TOGGLE: i32 = 2;
+pub const FL_MENU_VALUE: i32 = 4;
+pub const FL_MENU_RADIO: i32 = 8;
+pub const FL_MENU_INVISIBLE: i32 = 0x10;
+pub const FL_SUBMENU: i32 = 0x40;
+pub const FL_MENU_DIVIDER: i32 = 0x80;
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum MenuItemKind {
+    #[default]
+    Normal,
+    Toggle,
+    Radio,
+    Submenu,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct MenuItem {
+    /// Variable name of the item, empty when unnamed
+    pub name: String,
+    pub label: String,
+    pub kind: MenuItemKind,
+    /// Whether a toggle or radio item is on
+    pub checked: bool,
+    /// Whether a line is drawn below the item
+    pub divider: bool,
+    pub inactive: bool,
+    pub hidden: bool,
+    pub shortcut: Option<Shortcut>,
+    pub callback: Option<String>,
+    pub user_data: Option<String>,
+    /// Items of a submenu
+    pub children: Vec<MenuItem>,
+}
+
+/// A menu built from the `MenuItem` and `Submenu` children of a menu widget
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Menu {
+    pub items: Vec<MenuItem>,
+}
+
+/// An entry of the `Fl_Menu_Item` array, `label` is `None` for the terminators
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct FlatMenuItem {
+    pub label: Option<String>,
+    /// FLTK's encoding of the shortcut
+    pub shortcut: u32,
+    pub callback: Option<String>,
+    pub user_data: Option<String>,
+    pub flags: i32,
+}
+
+impl Widget {
+    /// Menu of a menu bar, choice or menu button, `None` for other widgets
+    pub fn menu(&self) -> Option<Menu> {
+        if !MENU_WIDGETS.contains(&self.typ.as_str()) {
+            return None;
+        }
+        Some(Menu::from_widgets(&self.children))
+    }
+}
+
+impl MenuItem {
+    /// Converts a menu item node, `None` when `w` isn't one
+    pub fn from_widget(w: &Widget) -> Option<MenuItem> {
+        let kind = match (w.typ.as_str(), w.props.typ.as_deref()) {
+            ("Submenu", _) => MenuItemKind::Submenu,
+            ("Checkbox", _) | ("MenuItem", Some("Toggle")) => MenuItemKind::Toggle,
+            ("RadioMenuItem", _) | ("MenuItem", Some("Radio")) => MenuItemKind::Radio,
+            ("MenuItem", _) => MenuItemKind::Normal,
+            _ => return None,
+        };
+        let p = &w.props;
+        Some(MenuItem {
+            name: w.name.clone(),
+            label: p.label.clone().unwrap_or_default(),
+            kind,
+            checked: kind != MenuItemKind::Submenu
+                && p.value.as_deref().is_some_and(|v| v.trim() != "0"),
+            divider: p.divider.unwrap_or(false),
+            inactive: p.deactivate.unwrap_or(false),
+            hidden: p.hide.unwrap_or(false),
+            shortcut: p.shortcut,
+            callback: p.callback.clone(),
+            user_data: p.user_data.clone(),
+            children: match kind {
+                MenuItemKind::Submenu => Menu::from_widgets(&w.children).items,
+                _ => vec![],
+            },
+        })
+    }
+
+    /// Flags of the item's `Fl_Menu_Item`
+    pub fn flags(&self) -> i32 {
+        let mut flags = match self.kind {
+            MenuItemKind::Normal => 0,
+            MenuItemKind::Toggle => FL_MENU_TOGGLE,
+            MenuItemKind::Radio => FL_MENU_RADIO,
+            MenuItemKind::Submenu => FL_SUBMENU,
+        };
+        if self.checked {
+            flags |= FL_MENU_VALUE;
+        }
+        if self.divider {
+            flags |= FL_MENU_DIVIDER;
+        }
+        if self.inactive {
+            flags |= FL_MENU_INACTIVE;
+        }
+        if self.hidden {
+            flags |= FL_MENU_INVISIBLE;
+        }
+        flags
+    }
+}
+
+impl Menu {
+    /// Builds a menu from menu item nodes, other widgets are skipped
+    pub fn from_widgets(widgets: &[Widget]) -> Menu {
+        Menu {
+            items: widgets.iter().filter_map(MenuItem::from_widget).collect(),
+        }
+    }
+
+    /// Flattens the menu into the layout of an `Fl_Menu_Item` array, where the
+    /// items of a submenu follow it and end with a terminator, as does the array
+    pub fn to_fl_menu_items(&self) -> Vec<FlatMenuItem> {
+        let mut out = vec![];
+        flatten(&self.items, &mut out);
+        out
+    }
+
+    /// Paths of the items in the notation of `Fl_Menu_::add`, like `File/Open`,
+    /// with slashes in labels escaped. Submenus are only listed when empty.
+    pub fn paths(&self) -> Vec<(String, &MenuItem)> {
+        let mut out = vec![];
+        collect_paths(&self.items, "", &mut out);
+        out
+    }
+}
+
+fn flatten(items: &[MenuItem], out: &mut Vec<FlatMenuItem>) {
+    for item in items {
+        out.push(FlatMenuItem {
+            label: Some(item.label.clone()),
+            shortcut: item.shortcut.map_or(0, Shortcut::to_fltk),
+            callback: item.callback.clone(),
+            user_data: item.user_data.clone(),
+            flags: item.flags(),
+        });
+        if item.kind == MenuItemKind::Submenu {
+            flatten(&item.children, out);
+        }
+    }
+    out.push(FlatMenuItem::default());
+}
+
+fn collect_paths<'a>(items: &'a [MenuItem], prefix: &str, out: &mut Vec<(String, &'a MenuItem)>) {
+    for item in items {
+        let path = format!("{}{}", prefix, item.label.replace('/', "\\/"));
+        if item.kind == MenuItemKind::Submenu && !item.children.is_empty() {
+            collect_paths(&item.children, &format!("{}/", path), out);
+        } else {
+            out.push((path, item));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_ok;
+
+    fn menu() -> Menu {
+        let a = parse_ok(include_str!("../fl_tests/menu.fl"));
+        let win = a.functions().next().unwrap().widgets().next().unwrap();
+        assert_eq!(win.menu(), None);
+        win.children[0].menu().unwrap()
+    }
+
+    #[test]
+    fn menu_items() {
+        let m = menu();
+        assert_eq!(m.items.len(), 2);
+        let file = &m.items[0];
+        assert_eq!(file.kind, MenuItemKind::Submenu);
+        assert_eq!(file.label, "File");
+        let open = &file.children[0];
+        assert_eq!(open.kind, MenuItemKind::Normal);
+        assert!(open.divider);
+        assert_eq!(open.shortcut.map(|s| s.to_string()).as_deref(), Some("Ctrl+O"));
+        assert_eq!(open.callback.as_deref(), Some("open_cb"));
+        let save = &file.children[1];
+        assert!(save.inactive && !save.divider);
+        assert_eq!(save.user_data.as_deref(), Some("1"));
+        assert_eq!(save.shortcut.map(Shortcut::to_fltk), Some(0x50073));
+        let view: Vec<_> = m.items[1]
+            .children
+            .iter()
+            .map(|i| (i.label.as_str(), i.kind, i.checked))
+            .collect();
+        assert_eq!(
+            view,
+            [
+                ("Toolbar", MenuItemKind::Toggle, true),
+                ("Statusbar", MenuItemKind::Toggle, false),
+                ("Small", MenuItemKind::Radio, true),
+                ("Large", MenuItemKind::Radio, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn flattened_array() {
+        let flat: Vec<_> = menu()
+            .to_fl_menu_items()
+            .into_iter()
+            .map(|i| (i.label, i.shortcut, i.flags))
+            .collect();
+        let item = |l: &str, s, f| (Some(l.to_string()), s, f);
+        assert_eq!(
+            flat,
+            [
+                item("File", 0, FL_SUBMENU),
+                item("Open", 0x4006f, FL_MENU_DIVIDER),
+                item("Save/Export", 0x50073, FL_MENU_INACTIVE),
+                item("Quit", 0x40071, 0),
+                (None, 0, 0),
+                item("View", 0, FL_SUBMENU),
+                item("Toolbar", 0, FL_MENU_TOGGLE | FL_MENU_VALUE),
+                item("Statusbar", 0, FL_MENU_TOGGLE),
+                item("Small", 0, FL_MENU_RADIO | FL_MENU_VALUE),
+                item("Large", 0, FL_MENU_RADIO),
+                (None, 0, 0),
+                (None, 0, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn menu_paths() {
+        let m = menu();
+        let paths: Vec<_> = m.paths().into_iter().map(|(p, _)| p).collect();
+        assert_eq!(
+            paths,
+            [
+                "File/Open",
+                "File/Save\\/Export",
+                "File/Quit",
+                "View/Toolbar",
+                "View/Statusbar",
+                "View/Small",
+                "View/Large",
+            ]
+        );
+    }
+}
@@ -1,11 +1,51 @@
 use crate::ast::*;
-use crate::lexer::Lexer;
+use crate::error::{Diagnostic, ParseError, Severity, ValueError};
+use crate::header::{Header, I18nConfig};
+use crate::layout::FlexLayout;
+use crate::lexer::{self, Lexer};
+use crate::span::Span;
+use crate::subtype;
 use crate::token::{Token, TokenType};
+use crate::widgets;
+use std::str::FromStr;
+
+/// Maximum depth of nested widgets, blocks and classes before the parser bails
+/// out, low enough for a debug build to stay within a 2 MB thread stack
+const MAX_NESTING: usize = 64;
+
+/// Keywords which, at the start of a line, are used to resynchronize after an error
+const SYNC_KEYWORDS: &[&str] = &["Function", "class", "decl", "widget_class"];
+
+/// Node keywords, anything else shaped like `Type name {...}` is a widget
+const KEYWORDS: &[&str] = &[
+    "Function",
+    "class",
+    "widget_class",
+    "comment",
+    "decl",
+    "declblock",
+    "codeblock",
+    "code",
+    "data",
+];
+
+/// Where a node appears, which determines the kinds of children it accepts
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Context {
+    TopLevel,
+    Class,
+    /// Function and code block bodies
+    Body,
+}
 
 pub struct Parser<'a> {
     lexer: Lexer<'a>,
     pub i: usize,
     pub tokens: Vec<Token<'a>>,
+    nesting: usize,
+    recover: bool,
+    unwinding: bool,
+    diagnostics: Vec<Diagnostic>,
 }
 
 impl<'a> Parser<'a> {
@@ -20,538 +60,1275 @@ impl<'a> Parser<'a> {
             lexer,
             i: 0,
             tokens,
+            nesting: 0,
+            recover: false,
+            unwinding: false,
+            diagnostics: vec![],
         }
     }
+    /// Parses the token stream, panicking on malformed input.
+    /// Prefer `try_parse` when handling untrusted files.
     pub fn parse(&mut self) -> Ast {
+        match self.try_parse() {
+            Ok(a) => a,
+            Err(e) => panic!("{}", e),
+        }
+    }
+    pub fn try_parse(&mut self) -> Result<Ast, ParseError> {
+        self.recover = false;
+        self.consume_items()
+    }
+    /// Parses the token stream, recovering from errors by skipping the
+    /// offending node. Returns the partial `Ast` along with every problem found.
+    pub fn parse_with_diagnostics(&mut self) -> (Ast, Vec<Diagnostic>) {
+        self.recover = true;
+        let a = match self.consume_items() {
+            Ok(a) => a,
+            Err(e) => {
+                self.diagnostics.push(e.into());
+                Ast::default()
+            }
+        };
+        (a, std::mem::take(&mut self.diagnostics))
+    }
+    fn consume_items(&mut self) -> Result<Ast, ParseError> {
         let mut a = Ast::default();
-        while self.i < self.tokens.len() {
-            let curr = self.tokens[self.i];
+        self.diagnostics.clear();
+        loop {
+            self.unwinding = false;
+            let curr = self.peek();
             match curr.typ {
                 TokenType::Eof => break,
                 TokenType::Word => match curr.word {
-                    "i18n_type" => {
-                        a.i18n_type = Some(true);
-                        self.i += 2;
-                    }
-                    "class" => {
-                        let c = self.consume_class();
-                        a.classes.push(c);
-                    }
-                    "Function" => {
-                        let f = self.consume_func();
-                        a.functions.push(f);
+                    "version"
+                    | "header_name"
+                    | "code_name"
+                    | "avoid_early_includes"
+                    | "do_not_include_H_from_C"
+                    | "include_H_from_C"
+                    | "use_FL_COMMAND"
+                    | "utf8_in_src"
+                    | "format_code"
+                    | "snap"
+                    | "gridx"
+                    | "gridy" => {
+                        self.recovering(|p| p.consume_header_item(&mut a.header))?;
                     }
-                    "comment" => {
-                        let c = self.consume_comment();
-                        a.comments.push(c);
+                    "i18n_type"
+                    | "i18n_include"
+                    | "i18n_conditional"
+                    | "i18n_function"
+                    | "i18n_gnu_function"
+                    | "i18n_static_function"
+                    | "i18n_gnu_static_function"
+                    | "i18n_file"
+                    | "i18n_pos_file"
+                    | "i18n_set"
+                    | "i18n_pos_set" => {
+                        self.recovering(|p| p.consume_i18n_item(&mut a.i18n))?;
                     }
-                    "decl" => {
-                        let d = self.consume_decl();
-                        a.decls.push(d);
+                    _ => {
+                        let item = self.consume_item(curr, Context::TopLevel)?;
+                        a.items.extend(item);
                     }
-                    "widget_class" => {
-                        let w = self.consume_widget();
-                        a.widget_classes.push(w);
-                    }
-                    _ => (),
                 },
-                _ => (),
+                TokenType::OpenBrace => {
+                    self.recovering(Self::skip_group)?;
+                }
+                _ => {
+                    let e = self.unexpected(curr, "top-level item");
+                    self.report(e)?;
+                    self.bump();
+                }
             }
-            self.i += 1;
         }
-        a
+        widgets::resolve_kinds(&mut a);
+        a.fill_kind_vecs();
+        Ok(a)
     }
-    fn consume_func(&mut self) -> Function {
-        let mut f = Function::default();
-        self.i += 1;
-        f.name = self.consume_braced_string();
-        self.i += 1; // opening parens of props
-        while self.tokens[self.i].typ != TokenType::Eof {
-            self.i += 1;
-            if self.tokens[self.i].typ == TokenType::CloseBrace {
-                break;
+    fn consume_header_item(&mut self, h: &mut Header) -> Result<(), ParseError> {
+        match self.bump().word {
+            "version" => h.version = self.consume_number("version")?,
+            "header_name" => h.header_name = Some(self.consume_string("header file name")?),
+            "code_name" => h.code_name = Some(self.consume_string("code file name")?),
+            "avoid_early_includes" => h.avoid_early_includes = true,
+            "do_not_include_H_from_C" => h.include_h_from_c = false,
+            "include_H_from_C" => h.include_h_from_c = true,
+            "use_FL_COMMAND" => h.use_fl_command = true,
+            "utf8_in_src" => h.utf8_in_src = true,
+            "format_code" => h.format_code = true,
+            // layout settings which only matter to the designer
+            "snap" => self.skip_group()?,
+            "gridx" | "gridy" => {
+                self.consume_word("grid size")?;
             }
-            match self.tokens[self.i].word {
+            _ => (),
+        }
+        Ok(())
+    }
+    /// Settings are applied to the configuration selected by a preceding `i18n_type`
+    fn consume_i18n_item(&mut self, i18n: &mut I18nConfig) -> Result<(), ParseError> {
+        let t = self.bump();
+        if t.word == "i18n_type" {
+            let v = self.peek();
+            match self.consume_number("i18n_type")? {
+                Some(1) => *i18n = I18nConfig::Gettext(Default::default()),
+                Some(2) => *i18n = I18nConfig::Catgets(Default::default()),
+                Some(0) => *i18n = I18nConfig::None,
+                Some(n) => {
+                    let e = self.error(v, "i18n type", format!("Unknown i18n type `{}`", n));
+                    self.report(e)?;
+                }
+                None => (),
+            }
+            return Ok(());
+        }
+        let value = self.consume_string("i18n setting")?;
+        match (i18n, t.word) {
+            (I18nConfig::Gettext(g), "i18n_include") => g.include = value,
+            (I18nConfig::Gettext(g), "i18n_conditional") => g.conditional = value,
+            (I18nConfig::Gettext(g), "i18n_function" | "i18n_gnu_function") => g.function = value,
+            (I18nConfig::Gettext(g), "i18n_static_function" | "i18n_gnu_static_function") => {
+                g.static_function = value
+            }
+            (I18nConfig::Catgets(c), "i18n_include") => c.include = value,
+            (I18nConfig::Catgets(c), "i18n_conditional") => c.conditional = value,
+            (I18nConfig::Catgets(c), "i18n_file" | "i18n_pos_file") => c.file = value,
+            (I18nConfig::Catgets(c), "i18n_set" | "i18n_pos_set") => c.set = value,
+            _ => self.warn(
+                t,
+                format!("Ignoring `{}` for the selected i18n type", t.word),
+            ),
+        }
+        Ok(())
+    }
+    fn consume_func(&mut self) -> Result<Function, ParseError> {
+        let start = self.i;
+        let mut f = Function::default();
+        self.bump();
+        let value_start = self.i;
+        f.name = self.consume_string("function name")?;
+        f.name_span = self.span_since(value_start);
+        self.expect(TokenType::OpenBrace, "`{`")?;
+        while let Some(t) = self.next_prop()? {
+            let value_start = self.i;
+            match t.word {
                 "open" => f.props.open = Some(true),
                 "C" => f.props.c = Some(true),
                 "protected" => f.props.visibility = Some(Visibility::PROTECTED),
                 "private" => f.props.visibility = Some(Visibility::PRIVATE),
-                "comment" => {
-                    self.i += 1;
-                    if self.tokens[self.i].typ == TokenType::OpenBrace {
-                        f.props.comment = Some(self.consume_braced_string());
-                    } else {
-                        f.props.comment = Some(self.tokens[self.i].word.to_string());
-                    }
-                }
+                "comment" => f.props.comment = Some(self.consume_string("comment")?),
                 "return_type" => {
-                    self.i += 1;
-                    if self.tokens[self.i].typ == TokenType::OpenBrace {
-                        f.props.return_type = Some(self.consume_braced_string());
-                    } else {
-                        f.props.return_type = Some(self.tokens[self.i].word.to_string());
-                    }
+                    f.props.return_type = Some(self.consume_string("return type")?);
                 }
-                _ => (),
+                _ => continue,
             }
+            let span = self.value_span(value_start, t);
+            f.props.spans.insert(t.word.to_string(), span);
+        }
+        if self.peek().typ == TokenType::OpenBrace {
+            f.children = self.consume_children(Context::Body)?;
         }
-        self.i += 1; // close props parens
-        if self.tokens[self.i].typ == TokenType::OpenBrace {
-            while self.tokens[self.i].typ != TokenType::CloseBrace {
-                self.i += 1;
-                if self.tokens[self.i].word == "code" {
-                    self.i += 1;
-                    f.code = Some(self.consume_code());
-                    self.i += 1;
+        f.span = self.span_since(start);
+        Ok(f)
+    }
+    /// Consumes the node starting at keyword `t`, skipping nodes which
+    /// are unknown or not allowed in `ctx`
+    fn consume_item(&mut self, t: Token<'a>, ctx: Context) -> Result<Option<Item>, ParseError> {
+        use Context::*;
+        let item = match (t.word, ctx) {
+            ("class", TopLevel | Class) => self.recovering(Self::consume_class)?.map(Item::Class),
+            ("Function", TopLevel | Class) => {
+                self.recovering(Self::consume_func)?.map(Item::Function)
+            }
+            ("widget_class", TopLevel | Class) => self
+                .recovering(Self::consume_widget_class)?
+                .map(|w| Item::WidgetClass(Box::new(w))),
+            ("comment", _) => self.recovering(Self::consume_comment)?.map(Item::Comment),
+            ("decl", _) => self.recovering(Self::consume_decl)?.map(Item::Decl),
+            ("codeblock", _) => self
+                .recovering(Self::consume_codeblock)?
+                .map(Item::CodeBlock),
+            ("declblock", _) => self
+                .recovering(|p| p.consume_declblock(ctx))?
+                .map(Item::DeclBlock),
+            ("data", _) => self.recovering(Self::consume_data)?.map(Item::Data),
+            ("code", Body) => self.recovering(Self::consume_code)?.map(Item::Code),
+            (w, Body) if !KEYWORDS.contains(&w) && self.at_widget() => self
+                .recovering(Self::consume_widget)?
+                .map(|w| Item::Widget(Box::new(w))),
+            (_, TopLevel) => {
+                self.warn(t, format!("Skipping unknown item `{}`", t.word));
+                self.recovering(Self::skip_node)?;
+                None
+            }
+            _ => {
+                self.skip_unsupported(t)?;
+                None
+            }
+        };
+        Ok(item)
+    }
+    /// Consumes a braced list of child nodes in source order
+    fn consume_children(&mut self, ctx: Context) -> Result<Vec<Item>, ParseError> {
+        let mut children = vec![];
+        self.expect(TokenType::OpenBrace, "`{`")?;
+        while let Some(t) = self.peek_child()? {
+            let item = self.consume_item(t, ctx)?;
+            children.extend(item);
+        }
+        Ok(children)
+    }
+    fn consume_codeblock(&mut self) -> Result<CodeBlock, ParseError> {
+        self.nested(|p| {
+            let start = p.i;
+            let mut c = CodeBlock::default();
+            p.bump();
+            let value_start = p.i;
+            c.code = p.consume_string("code")?;
+            c.code_span = p.span_since(value_start);
+            p.expect(TokenType::OpenBrace, "`{`")?;
+            while let Some(t) = p.next_prop()? {
+                let value_start = p.i;
+                match t.word {
+                    "open" => c.props.open = Some(true),
+                    "after" => c.props.after = Some(p.consume_string("code")?),
+                    _ => continue,
                 }
-                if self.tokens[self.i].word.starts_with("Fl_")
-                    || self.tokens[self.i].word == "MenuItem"
-                    || self.tokens[self.i].word == "Submenu"
-                {
-                    let w = self.consume_widget();
-                    f.widgets.push(w);
-                    self.i += 1;
+                let span = p.value_span(value_start, t);
+                c.props.spans.insert(t.word.to_string(), span);
+            }
+            if p.peek().typ == TokenType::OpenBrace {
+                c.children = p.consume_children(Context::Body)?;
+            }
+            c.span = p.span_since(start);
+            Ok(c)
+        })
+    }
+    /// Children of a declaration block belong to the block's own context
+    fn consume_declblock(&mut self, ctx: Context) -> Result<DeclBlock, ParseError> {
+        self.nested(|p| {
+            let start = p.i;
+            let mut d = DeclBlock::default();
+            p.bump();
+            let value_start = p.i;
+            d.decl = p.consume_string("declaration")?;
+            d.decl_span = p.span_since(value_start);
+            p.expect(TokenType::OpenBrace, "`{`")?;
+            while let Some(t) = p.next_prop()? {
+                let value_start = p.i;
+                match t.word {
+                    "open" => d.props.open = Some(true),
+                    "public" => d.props.visibility = Some(Visibility::PUBLIC),
+                    "private" => d.props.visibility = Some(Visibility::PRIVATE),
+                    "protected" => d.props.visibility = Some(Visibility::PROTECTED),
+                    "in_source" => d.props.in_source = Some(true),
+                    "in_header" => d.props.in_header = Some(true),
+                    "after" => d.props.after = Some(p.consume_string("declaration")?),
+                    _ => continue,
                 }
+                let span = p.value_span(value_start, t);
+                d.props.spans.insert(t.word.to_string(), span);
             }
-        }
-        f
+            if p.peek().typ == TokenType::OpenBrace {
+                d.children = p.consume_children(ctx)?;
+            }
+            d.span = p.span_since(start);
+            Ok(d)
+        })
+    }
+    fn consume_widget(&mut self) -> Result<Widget, ParseError> {
+        self.nested(Self::consume_widget_inner)
     }
-    fn consume_widget(&mut self) -> Widget {
+    fn consume_widget_inner(&mut self) -> Result<Widget, ParseError> {
+        let start = self.i;
         let mut w = Widget {
-            typ: self.tokens[self.i].word.to_string(),
+            typ: self.bump().word.to_string(),
             ..Default::default()
         };
-        self.i += 1;
-        if self.tokens[self.i].typ == TokenType::OpenBrace {
-            self.i += 1;
+        let value_start = self.i;
+        w.name = self.consume_string("widget name")?;
+        w.name_span = self.span_since(value_start);
+        self.expect(TokenType::OpenBrace, "`{`")?;
+        while let Some(t) = self.next_prop()? {
+            self.consume_widget_prop(t, &mut w.props)?;
         }
-        if !self.tokens[self.i].word.is_empty() {
-            w.name = self.tokens[self.i].word.to_string();
-        } else {
-            w.name = String::new();
+        self.check_subtype(&w);
+        if self.peek().typ == TokenType::OpenBrace {
+            w.children = self.consume_widget_children()?;
         }
-        while self.tokens[self.i].typ != TokenType::Eof {
-            self.i += 1;
-            if self.tokens[self.i].typ == TokenType::CloseBrace {
-                break;
-            }
-            match self.tokens[self.i].word {
-                "open" => w.props.open = Some(true),
-                "hide" => w.props.hide = Some(true),
-                "deactivate" => w.props.deactivate = Some(true),
-                "divider" => w.props.divider = Some(true),
-                "resizable" => w.props.resizable = Some(true),
-                "visible" => w.props.visible = Some(true),
-                "hotspot" => w.props.hotspot = Some(true),
-                "modal" => w.props.modal = Some(true),
-                "non_modal" => w.props.non_modal = Some(true),
-                "noborder" => w.props.noborder = Some(true),
-                "xywh" => {
-                    self.i += 1;
-                    w.props.xywh = self.consume_braced_string();
-                }
-                "size_range" => {
-                    self.i += 1;
-                    w.props.size_range = Some(self.consume_braced_string());
-                }
-                "color" => {
-                    self.i += 1;
-                    w.props.color = Some(self.tokens[self.i].word.to_string().parse().unwrap_or_else(|_| panic!("Failed to parse numeric value for `align` property at token:\"{:?}\"!",
-                            self.tokens[self.i])));
-                }
-                "selection_color" => {
-                    self.i += 1;
-                    w.props.selection_color = Some(
-                        self.tokens[self.i]
-                            .word
-                            .to_string()
-                            .parse()
-                            .unwrap_or_else(|_| panic!("Failed to parse numeric value for `selection_color` property at token:\"{:?}\"!",
-                            self.tokens[self.i])),
-                    );
-                }
-                "labelcolor" => {
-                    self.i += 1;
-                    w.props.labelcolor = Some(self.tokens[self.i].word.to_string().parse().unwrap_or_else(|_| panic!("Failed to parse numeric value for `labelcolor` property at token:\"{:?}\"!",
-                            self.tokens[self.i])));
-                }
-                "textcolor" => {
-                    self.i += 1;
-                    w.props.textcolor = Some(self.tokens[self.i].word.to_string().parse().unwrap_or_else(|_| panic!("Failed to parse numeric value for `textcolor` property at token:\"{:?}\"!",
-                            self.tokens[self.i])));
-                }
-                "type" => {
-                    self.i += 1;
-                    if self.tokens[self.i].typ == TokenType::OpenBrace {
-                        w.props.typ = Some(self.consume_braced_string());
-                    } else {
-                        w.props.typ = Some(self.tokens[self.i].word.to_string());
-                    }
-                }
-                "labeltype" => {
-                    self.i += 1;
-                    w.props.labeltype = Some(self.tokens[self.i].word.to_string());
-                }
-                "labelfont" => {
-                    self.i += 1;
-                    w.props.labelfont = Some(self.tokens[self.i].word.to_string().parse().unwrap_or_else(|_| panic!("Failed to parse numeric value for `labeltype` property at token:\"{:?}\"!",
-                            self.tokens[self.i])));
-                }
-                "textfont" => {
-                    self.i += 1;
-                    w.props.textfont = Some(self.tokens[self.i].word.to_string().parse().unwrap_or_else(|_| panic!("Failed to parse numeric value for `textfont` property at token:\"{:?}\"!",
-                            self.tokens[self.i])));
-                }
-                "labelsize" => {
-                    self.i += 1;
-                    w.props.labelsize = Some(self.tokens[self.i].word.to_string().parse().unwrap_or_else(|_| panic!("Failed to parse numeric value for `labelsize` property at token:\"{:?}\"!",
-                            self.tokens[self.i])));
-                }
-                "textsize" => {
-                    self.i += 1;
-                    w.props.textsize = Some(self.tokens[self.i].word.to_string().parse().unwrap_or_else(|_| panic!("Failed to parse numeric value for `textsize` property at token:\"{:?}\"!",
-                            self.tokens[self.i])));
-                }
-                "box" => {
-                    self.i += 1;
-                    w.props.r#box = Some(self.tokens[self.i].word.to_string());
-                }
-                "down_box" => {
-                    self.i += 1;
-                    w.props.down_box = Some(self.tokens[self.i].word.to_string());
-                }
-                "align" => {
-                    self.i += 1;
-                    w.props.align = Some(self.tokens[self.i].word.to_string().parse().unwrap_or_else(|_| panic!("Failed to parse numeric value for `align` property at token:\"{:?}\"!",
-                            self.tokens[self.i])));
-                }
-                "when" => {
-                    self.i += 1;
-                    w.props.when = Some(self.tokens[self.i].word.to_string().parse().unwrap_or_else(|_| panic!("Failed to parse numeric value for `when` property at token:\"{:?}\"!",
-                            self.tokens[self.i])));
-                }
-                "shortcut" => {
-                    self.i += 1;
-                    w.props.shortcut = Some(self.tokens[self.i].word.to_string());
-                }
-                "gap" => {
-                    self.i += 1;
-                    if self.tokens[self.i].typ == TokenType::OpenBrace {
-                        w.props.gap = Some(self.consume_braced_string());
-                    } else {
-                        w.props.gap = Some(self.tokens[self.i].word.to_string());
-                    }
-                }
-                "minimum" => {
-                    self.i += 1;
-                    w.props.minimum = Some(self.tokens[self.i].word.to_string().parse().unwrap_or_else(|_| panic!("Failed to parse numeric value for `minimum` property at token:\"{:?}\"!",
-                            self.tokens[self.i])));
-                }
-                "maximum" => {
-                    self.i += 1;
-                    w.props.maximum = Some(self.tokens[self.i].word.to_string().parse().unwrap_or_else(|_| panic!("Failed to parse numeric value for `maximum` property at token:\"{:?}\"!",
-                            self.tokens[self.i])));
-                }
-                "step" => {
-                    self.i += 1;
-                    w.props.step = Some(self.tokens[self.i].word.to_string().parse().unwrap_or_else(|_| panic!("Failed to parse numeric value for `step` property at token:\"{:?}\"!",
-                            self.tokens[self.i])));
-                }
-                "slider_size" => {
-                    self.i += 1;
-                    w.props.slider_size = Some(
-                        self.tokens[self.i]
-                            .word
-                            .to_string()
-                            .parse()
-                            .unwrap_or_else(|_| panic!("Failed to parse numeric value for `slider_size` property at token:\"{:?}\"!",
-                            self.tokens[self.i])),
-                    );
+        self.check_flex(&w);
+        w.span = self.span_since(start);
+        Ok(w)
+    }
+    /// Warns about `type` values unknown for built-in widgets
+    fn check_subtype(&mut self, w: &Widget) {
+        if let (Some(typ), Some(span)) = (&w.props.typ, w.props.spans.get("type")) {
+            if widgets::is_builtin(&w.typ) {
+                if let Err(e) = subtype::decode_type(&w.typ, typ) {
+                    self.diagnostics.push(Diagnostic {
+                        severity: Severity::Warning,
+                        span: *span,
+                        message: e.message,
+                    });
                 }
-                "size" => {
-                    self.i += 1;
-                    w.props.size = Some(self.tokens[self.i].word.to_string().parse().unwrap_or_else(|_| panic!("Failed to parse numeric value for `size` property at token:\"{:?}\"!",
-                            self.tokens[self.i])));
+            }
+        }
+    }
+    /// Warns about fixed size indices of an `Fl_Flex` which match no child
+    fn check_flex(&mut self, w: &Widget) {
+        if w.typ != "Fl_Flex" {
+            return;
+        }
+        let span = match ["set_size_tuples", "fixed_size_tuples"]
+            .iter()
+            .find_map(|p| w.props.spans.get(*p))
+        {
+            Some(span) => *span,
+            None => return,
+        };
+        for index in FlexLayout::stray_indices(&w.props, w.children.len()) {
+            self.diagnostics.push(Diagnostic {
+                severity: Severity::Warning,
+                span,
+                message: format!(
+                    "Ignoring fixed size index {} out of range for {} children",
+                    index,
+                    w.children.len()
+                ),
+            });
+        }
+    }
+    /// Consumes the value of widget property `t`, unknown properties are ignored
+    fn consume_widget_prop(
+        &mut self,
+        t: Token<'a>,
+        props: &mut WidgetProps,
+    ) -> Result<(), ParseError> {
+        let value_start = self.i;
+        match t.word {
+            "open" => props.open = Some(true),
+            "hide" => props.hide = Some(true),
+            "deactivate" => props.deactivate = Some(true),
+            "divider" => props.divider = Some(true),
+            "resizable" => props.resizable = Some(true),
+            "visible" => props.visible = Some(true),
+            "hotspot" => props.hotspot = Some(true),
+            "modal" => props.modal = Some(true),
+            "non_modal" => props.non_modal = Some(true),
+            "noborder" => props.noborder = Some(true),
+            "xywh" => {
+                props.xywh = self.consume_braced_string()?;
+                props.rect = self.parse_value(value_start, &props.xywh)?;
+                if let Some(Err(e)) = props.rect.map(|r| r.validate()) {
+                    self.warn_since(value_start, e.message);
                 }
-                "label" => {
-                    self.i += 1;
-                    if self.tokens[self.i].typ == TokenType::OpenBrace {
-                        w.props.label = Some(self.consume_braced_string());
-                    } else {
-                        w.props.label = Some(self.tokens[self.i].word.to_string());
-                    }
+            }
+            "size_range" => {
+                let raw = self.consume_braced_string()?;
+                props.size_limits = self.parse_value(value_start, &raw)?;
+                props.size_range = Some(raw);
+                if let Some(Err(e)) = props.size_limits.map(|r| r.validate()) {
+                    self.warn_since(value_start, e.message);
                 }
-                "xclass" => {
-                    self.i += 1;
-                    if self.tokens[self.i].typ == TokenType::OpenBrace {
-                        w.props.xclass = Some(self.consume_braced_string());
-                    } else {
-                        w.props.xclass = Some(self.tokens[self.i].word.to_string());
-                    }
+            }
+            "color" => props.color = self.consume_value("color")?,
+            "selection_color" => props.selection_color = self.consume_value("color")?,
+            "labelcolor" => props.labelcolor = self.consume_value("color")?,
+            "textcolor" => props.textcolor = self.consume_value("color")?,
+            "type" => props.typ = Some(self.consume_string("widget type")?),
+            "labeltype" => props.labeltype = self.consume_value("label type")?,
+            "labelfont" => props.labelfont = self.consume_value("font")?,
+            "textfont" => props.textfont = self.consume_value("font")?,
+            "labelsize" => props.labelsize = self.consume_number("labelsize")?,
+            "textsize" => props.textsize = self.consume_number("textsize")?,
+            "box" => props.r#box = self.consume_value("box type")?,
+            "down_box" => props.down_box = self.consume_value("box type")?,
+            "align" => props.align = self.consume_value("alignment")?,
+            "when" => props.when = self.consume_value("when")?,
+            "shortcut" => props.shortcut = self.consume_value("shortcut")?,
+            "gap" => props.gap = Some(self.consume_string("gap")?),
+            "minimum" => props.minimum = self.consume_number("minimum")?,
+            "maximum" => props.maximum = self.consume_number("maximum")?,
+            "step" => props.step = self.consume_number("step")?,
+            "slider_size" => props.slider_size = self.consume_number("slider_size")?,
+            "size" => props.size = self.consume_number("size")?,
+            "label" => props.label = Some(self.consume_string("label")?),
+            "xclass" => props.xclass = Some(self.consume_string("xclass")?),
+            "class" => props.class = Some(self.consume_string("class name")?),
+            "tooltip" => props.tooltip = Some(self.consume_string("tooltip")?),
+            "image" => props.image = Some(self.consume_string("image")?),
+            "deimage" => props.deimage = Some(self.consume_string("image")?),
+            "value" => props.value = Some(self.consume_string("value")?),
+            "set_size_tuples" | "fixed_size_tuples" => {
+                props.size_tuple = Some(self.consume_braced_string()?);
+            }
+            "margins" => props.margins = Some(self.consume_braced_string()?),
+            "dimensions" => props.dimensions = Some(self.consume_braced_string()?),
+            "margin" => props.margin = Some(self.consume_braced_string()?),
+            "rowheights" => props.rowheights = Some(self.consume_braced_string()?),
+            "rowweights" => props.rowweights = Some(self.consume_braced_string()?),
+            "rowgaps" => props.rowgaps = Some(self.consume_braced_string()?),
+            "colwidths" => props.colwidths = Some(self.consume_braced_string()?),
+            "colweights" => props.colweights = Some(self.consume_braced_string()?),
+            "colgaps" => props.colgaps = Some(self.consume_braced_string()?),
+            "code0" => props.code0 = Some(self.consume_braced_string()?),
+            "code1" => props.code1 = Some(self.consume_braced_string()?),
+            "code2" => props.code2 = Some(self.consume_braced_string()?),
+            "code3" => props.code3 = Some(self.consume_braced_string()?),
+            "extra_code" => props.extra_code = Some(self.consume_braced_string()?),
+            "callback" => props.callback = Some(self.consume_string("callback")?),
+            "user_data" => props.user_data = Some(self.consume_string("user data")?),
+            "user_data_type" => {
+                props.user_data_type = Some(self.consume_string("user data type")?);
+            }
+            "comment" => props.comment = Some(self.consume_string("comment")?),
+            "parent_properties" => {
+                props.parent_properties = Some(self.consume_parent_props()?);
+            }
+            _ => return Ok(()),
+        }
+        let span = self.value_span(value_start, t);
+        props.spans.insert(t.word.to_string(), span);
+        Ok(())
+    }
+    fn consume_widget_children(&mut self) -> Result<Vec<Widget>, ParseError> {
+        let mut children = vec![];
+        self.expect(TokenType::OpenBrace, "`{`")?;
+        while let Some(t) = self.peek_child()? {
+            if !KEYWORDS.contains(&t.word) && self.at_widget() {
+                let c = self.recovering(Self::consume_widget)?;
+                children.extend(c);
+            } else {
+                self.skip_unsupported(t)?;
+            }
+        }
+        Ok(children)
+    }
+    fn consume_widget_class(&mut self) -> Result<WidgetClass, ParseError> {
+        let start = self.i;
+        let mut w = WidgetClass::default();
+        self.bump();
+        let value_start = self.i;
+        w.name = self.consume_string("class name")?;
+        w.name_span = self.span_since(value_start);
+        self.expect(TokenType::OpenBrace, "`{`")?;
+        while let Some(t) = self.next_prop()? {
+            let value_start = self.i;
+            match t.word {
+                "class" => w.props.class = Some(self.consume_string("class name")?),
+                "position_relative" => w.props.position_relative = Some(true),
+                "position_relative_rescale" => {
+                    w.props.position_relative = Some(true);
+                    w.props.position_relative_rescale = Some(true);
                 }
-                "class" => {
-                    self.i += 1;
-                    if self.tokens[self.i].typ == TokenType::OpenBrace {
-                        w.props.class = Some(self.consume_braced_string());
-                    } else {
-                        w.props.class = Some(self.tokens[self.i].word.to_string());
-                    }
+                "public" => w.props.visibility = Some(Visibility::PUBLIC),
+                "private" => w.props.visibility = Some(Visibility::PRIVATE),
+                "protected" => w.props.visibility = Some(Visibility::PROTECTED),
+                "comment" => w.props.comment = Some(self.consume_string("comment")?),
+                _ => {
+                    self.consume_widget_prop(t, &mut w.widget_props)?;
+                    continue;
                 }
-                "tooltip" => {
-                    self.i += 1;
-                    if self.tokens[self.i].typ == TokenType::OpenBrace {
-                        w.props.tooltip = Some(self.consume_braced_string());
-                    } else {
-                        w.props.tooltip = Some(self.tokens[self.i].word.to_string());
-                    }
+            }
+            let span = self.value_span(value_start, t);
+            w.props.spans.insert(t.word.to_string(), span);
+        }
+        if self.peek().typ == TokenType::OpenBrace {
+            w.children = self.consume_widget_children()?;
+        }
+        w.span = self.span_since(start);
+        Ok(w)
+    }
+    fn consume_class(&mut self) -> Result<Class, ParseError> {
+        self.nested(Self::consume_class_inner)
+    }
+    fn consume_class_inner(&mut self) -> Result<Class, ParseError> {
+        let start = self.i;
+        let mut c = Class::default();
+        self.bump();
+        let value_start = self.i;
+        c.name = self.consume_string("class name")?;
+        c.name_span = self.span_since(value_start);
+        // a word following the name means the first word was a prefix like FL_EXPORT
+        if self.peek().typ == TokenType::Word {
+            let value_start = self.i;
+            let name = self.consume_word("class name")?;
+            c.name_span = self.span_since(value_start);
+            c.prefix = Some(std::mem::replace(&mut c.name, name));
+        }
+        // older files keep the base clause in the name, `{MyWin : public Base}`
+        if let Some((name, bases)) = c.name.split_once(':') {
+            c.props.subclass_of = Some(bases.trim().to_string());
+            c.name = name.trim().to_string();
+        }
+        self.expect(TokenType::OpenBrace, "`{`")?;
+        while let Some(t) = self.next_prop()? {
+            let value_start = self.i;
+            match t.word {
+                "open" => c.props.open = Some(true),
+                "protected" => c.props.visibility = Some(Visibility::PROTECTED),
+                "private" => c.props.visibility = Some(Visibility::PRIVATE),
+                "comment" => c.props.comment = Some(self.consume_string("comment")?),
+                ":" => c.props.subclass_of = Some(self.consume_base_clause()?),
+                _ => continue,
+            }
+            let span = self.value_span(value_start, t);
+            c.props.spans.insert(t.word.to_string(), span);
+        }
+        if let Some(bases) = &c.props.subclass_of {
+            c.bases = BaseClass::parse_list(bases);
+        }
+        if self.peek().typ == TokenType::OpenBrace {
+            self.bump();
+            while let Some(t) = self.peek_child()? {
+                let item = self.consume_item(t, Context::Class)?;
+                c.members.extend(item);
+            }
+        }
+        c.span = self.span_since(start);
+        Ok(c)
+    }
+    /// Consumes the base clause following `:`, either braced or as bare words
+    /// like `: public Base`, where each base may be preceded by specifiers
+    fn consume_base_clause(&mut self) -> Result<String, ParseError> {
+        if self.peek().typ == TokenType::OpenBrace {
+            return self.consume_braced_string();
+        }
+        let mut words = vec![];
+        loop {
+            let w = self.consume_word("base class")?;
+            let last = !matches!(w.as_str(), "public" | "protected" | "private" | "virtual")
+                && !w.ends_with(',');
+            words.push(w);
+            if last {
+                break;
+            }
+        }
+        Ok(words.join(" "))
+    }
+    fn consume_comment(&mut self) -> Result<Comment, ParseError> {
+        let start = self.i;
+        let mut c = Comment::default();
+        self.bump();
+        let value_start = self.i;
+        c.comment = self.consume_string("comment")?;
+        c.comment_span = self.span_since(value_start);
+        if self.peek().typ == TokenType::OpenBrace {
+            self.bump();
+            while let Some(t) = self.next_prop()? {
+                let value_start = self.i;
+                match t.word {
+                    "in_source" => c.props.in_source = Some(true),
+                    "in_header" => c.props.in_header = Some(true),
+                    _ => continue,
                 }
-                "image" => {
-                    self.i += 1;
-                    if self.tokens[self.i].typ == TokenType::OpenBrace {
-                        w.props.image = Some(self.consume_braced_string());
-                    } else {
-                        w.props.image = Some(self.tokens[self.i].word.to_string());
-                    }
+                let span = self.value_span(value_start, t);
+                c.props.spans.insert(t.word.to_string(), span);
+            }
+        }
+        c.span = self.span_since(start);
+        Ok(c)
+    }
+    fn consume_decl(&mut self) -> Result<Decl, ParseError> {
+        let start = self.i;
+        let mut d = Decl::default();
+        self.bump();
+        let value_start = self.i;
+        d.decl = self.consume_string("declaration")?;
+        d.decl_span = self.span_since(value_start);
+        if self.peek().typ == TokenType::OpenBrace {
+            self.bump();
+            while let Some(t) = self.next_prop()? {
+                let value_start = self.i;
+                match t.word {
+                    "private" => d.props.visibility = Visibility::PRIVATE,
+                    "public" => d.props.visibility = Visibility::PUBLIC,
+                    "protected" => d.props.visibility = Visibility::PROTECTED,
+                    "global" => d.props.global = Some(true),
+                    "local" => d.props.local = Some(true),
+                    _ => continue,
                 }
-                "deimage" => {
-                    self.i += 1;
-                    if self.tokens[self.i].typ == TokenType::OpenBrace {
-                        w.props.deimage = Some(self.consume_braced_string());
-                    } else {
-                        w.props.deimage = Some(self.tokens[self.i].word.to_string());
+                let span = self.value_span(value_start, t);
+                d.props.spans.insert(t.word.to_string(), span);
+            }
+        }
+        d.span = self.span_since(start);
+        Ok(d)
+    }
+    fn consume_data(&mut self) -> Result<Data, ParseError> {
+        let start = self.i;
+        let mut d = Data::default();
+        self.bump();
+        let value_start = self.i;
+        d.name = self.consume_string("variable name")?;
+        d.name_span = self.span_since(value_start);
+        self.expect(TokenType::OpenBrace, "`{`")?;
+        while let Some(t) = self.next_prop()? {
+            let value_start = self.i;
+            match t.word {
+                "public" => d.props.visibility = Some(Visibility::PUBLIC),
+                "private" => d.props.visibility = Some(Visibility::PRIVATE),
+                "protected" => d.props.visibility = Some(Visibility::PROTECTED),
+                "local" => d.props.local = Some(true),
+                "global" => d.props.global = Some(true),
+                "filename" => d.props.filename = Some(self.consume_string("file name")?),
+                "textmode" => d.props.mode = DataMode::Text,
+                "compressed" => d.props.mode = DataMode::Compressed,
+                "comment" => d.props.comment = Some(self.consume_string("comment")?),
+                _ => continue,
+            }
+            let span = self.value_span(value_start, t);
+            d.props.spans.insert(t.word.to_string(), span);
+        }
+        d.span = self.span_since(start);
+        Ok(d)
+    }
+    fn consume_code(&mut self) -> Result<Code, ParseError> {
+        let start = self.i;
+        let mut c = Code::default();
+        self.bump();
+        let value_start = self.i;
+        c.code = self.consume_braced_string()?;
+        c.code_span = self.span_since(value_start);
+        // skip the empty property list
+        if self.peek().typ == TokenType::OpenBrace {
+            self.skip_group()?;
+        }
+        c.span = self.span_since(start);
+        Ok(c)
+    }
+    fn consume_parent_props(&mut self) -> Result<ParentProps, ParseError> {
+        let mut p = ParentProps::default();
+        self.expect(TokenType::OpenBrace, "`{`")?;
+        while let Some(t) = self.next_prop()? {
+            let value_start = self.i;
+            match t.word {
+                "location" => p.location = Some(self.consume_braced_string()?),
+                "rowspan" => p.rowspan = self.consume_number("rowspan")?,
+                "colspan" => p.colspan = self.consume_number("colspan")?,
+                "align" => p.align = self.consume_number("align")?,
+                "min_size" => p.min_size = Some(self.consume_braced_string()?),
+                _ => continue,
+            }
+            let span = self.value_span(value_start, t);
+            p.spans.insert(t.word.to_string(), span);
+        }
+        Ok(p)
+    }
+
+    /// Consumes `{ ... }` and returns the unescaped text between the braces,
+    /// starting at the first token inside them. Callers record the span of the
+    /// value, braces included, which covers the raw text in the source.
+    fn consume_braced_string(&mut self) -> Result<String, ParseError> {
+        self.expect(TokenType::OpenBrace, "`{`")?;
+        let start = self.peek().start;
+        let mut openbrace = 1;
+        loop {
+            let t = self.peek();
+            match t.typ {
+                TokenType::OpenBrace => openbrace += 1,
+                TokenType::CloseBrace => openbrace -= 1,
+                TokenType::Eof => return Err(self.unexpected(t, "`}`")),
+                _ => (),
+            }
+            self.bump();
+            if openbrace == 0 {
+                return Ok(lexer::unescape(&self.lexer.s[start..t.start]));
+            }
+        }
+    }
+    /// Consumes either a bare word or a braced string
+    fn consume_string(&mut self, expected: &'static str) -> Result<String, ParseError> {
+        let t = self.peek();
+        match t.typ {
+            TokenType::OpenBrace => self.consume_braced_string(),
+            TokenType::Word => {
+                self.bump();
+                Ok(lexer::unescape(t.word))
+            }
+            _ => Err(self.unexpected(t, expected)),
+        }
+    }
+    fn consume_word(&mut self, expected: &'static str) -> Result<String, ParseError> {
+        let t = self.peek();
+        if t.typ != TokenType::Word {
+            return Err(self.unexpected(t, expected));
+        }
+        self.bump();
+        Ok(t.word.to_string())
+    }
+    /// Consumes a numeric property value. Malformed values are reported
+    /// and yield `None` when recovering from errors.
+    fn consume_number<T: FromStr>(&mut self, prop: &str) -> Result<Option<T>, ParseError> {
+        let t = self.peek();
+        if t.typ != TokenType::Word {
+            let e = self.unexpected(t, "numeric value");
+            self.report(e)?;
+            return Ok(None);
+        }
+        self.bump();
+        match t.word.parse() {
+            Ok(v) => Ok(Some(v)),
+            Err(_) => {
+                let e = self.error(
+                    t,
+                    "numeric value",
+                    format!(
+                        "Failed to parse numeric value for `{}` property, found `{}`",
+                        prop, t.word
+                    ),
+                );
+                self.report(e)?;
+                Ok(None)
+            }
+        }
+    }
+    /// Consumes a word and converts it into its typed form, see `parse_value`
+    fn consume_value<T: FromStr<Err = ValueError>>(
+        &mut self,
+        expected: &'static str,
+    ) -> Result<Option<T>, ParseError> {
+        let start = self.i;
+        let word = self.consume_word(expected)?;
+        self.parse_value(start, &word)
+    }
+    /// Converts the text of the value starting at token `start` into its typed
+    /// form. Invalid values are reported and yield `None` when recovering.
+    fn parse_value<T: FromStr<Err = ValueError>>(
+        &mut self,
+        start: usize,
+        s: &str,
+    ) -> Result<Option<T>, ParseError> {
+        match s.parse() {
+            Ok(v) => Ok(Some(v)),
+            Err(e) => {
+                let mut t = self.tokens.get(start).copied().unwrap_or_else(|| self.peek());
+                let span = self.span_since(start);
+                t.start = span.start;
+                t.end = span.end;
+                let e = self.error(t, "valid value", e.message);
+                self.report(e)?;
+                Ok(None)
+            }
+        }
+    }
+
+    /// Whether the tokens ahead have the shape of a widget, `Type name {`,
+    /// where the name is a word or a braced string
+    fn at_widget(&self) -> bool {
+        let typ = |i: usize| self.tokens.get(i).map(|t| t.typ);
+        let mut i = self.i + 1;
+        match typ(i) {
+            Some(TokenType::Word) => i += 1,
+            Some(TokenType::OpenBrace) => {
+                let mut depth = 0;
+                loop {
+                    match typ(i) {
+                        Some(TokenType::OpenBrace) => depth += 1,
+                        Some(TokenType::CloseBrace) => depth -= 1,
+                        Some(TokenType::Eof) | None => return false,
+                        _ => (),
                     }
-                }
-                "value" => {
-                    self.i += 1;
-                    if self.tokens[self.i].typ == TokenType::OpenBrace {
-                        w.props.value = Some(self.consume_braced_string());
-                    } else {
-                        w.props.value = Some(self.tokens[self.i].word.to_string());
+                    i += 1;
+                    if depth == 0 {
+                        break;
                     }
                 }
-                "set_size_tuples" => {
-                    self.i += 1;
-                    w.props.size_tuple = Some(self.consume_braced_string());
-                }
-                "margins" => {
-                    self.i += 1;
-                    w.props.margins = Some(self.consume_braced_string());
-                }
-                "dimensions" => {
-                    self.i += 1;
-                    w.props.dimensions = Some(self.consume_braced_string());
-                }
-                "margin" => {
-                    self.i += 1;
-                    w.props.margin = Some(self.consume_braced_string());
-                }
-                "fixed_size_tuples" => {
-                    self.i += 1;
-                    w.props.size_tuple = Some(self.consume_braced_string());
-                }
-                "code0" => {
-                    self.i += 1;
-                    w.props.code0 = Some(self.consume_braced_string());
-                }
-                "code1" => {
-                    self.i += 1;
-                    w.props.code1 = Some(self.consume_braced_string());
-                }
-                "code2" => {
-                    self.i += 1;
-                    w.props.code2 = Some(self.consume_braced_string());
-                }
-                "code3" => {
-                    self.i += 1;
-                    w.props.code3 = Some(self.consume_braced_string());
+            }
+            _ => return false,
+        }
+        typ(i) == Some(TokenType::OpenBrace)
+    }
+    /// Returns the next property word of a `{ ... }` property list,
+    /// or `None` after consuming the closing brace
+    fn next_prop(&mut self) -> Result<Option<Token<'a>>, ParseError> {
+        loop {
+            let t = self.peek();
+            match t.typ {
+                TokenType::Word => {
+                    self.bump();
+                    return Ok(Some(t));
                 }
-                "extra_code" => {
-                    self.i += 1;
-                    w.props.extra_code = Some(self.consume_braced_string());
+                TokenType::CloseBrace => {
+                    self.bump();
+                    return Ok(None);
                 }
-                "callback" => {
-                    self.i += 1;
-                    if self.tokens[self.i].typ == TokenType::OpenBrace {
-                        w.props.callback = Some(self.consume_braced_string());
-                    } else {
-                        w.props.callback = Some(self.tokens[self.i].word.to_string());
-                    }
+                TokenType::OpenBrace => self.skip_group()?,
+                _ => return Err(self.unexpected(t, "`}`")),
+            }
+        }
+    }
+    /// Returns the keyword starting the next child node without consuming it,
+    /// or `None` after consuming the closing brace of the children list
+    fn peek_child(&mut self) -> Result<Option<Token<'a>>, ParseError> {
+        if self.unwinding {
+            return Ok(None);
+        }
+        loop {
+            let t = self.peek();
+            match t.typ {
+                TokenType::Word => return Ok(Some(t)),
+                TokenType::CloseBrace => {
+                    self.bump();
+                    return Ok(None);
                 }
-                "user_data" => {
-                    self.i += 1;
-                    if self.tokens[self.i].typ == TokenType::OpenBrace {
-                        w.props.user_data = Some(self.consume_braced_string());
-                    } else {
-                        w.props.user_data = Some(self.tokens[self.i].word.to_string());
+                TokenType::OpenBrace => self.skip_group()?,
+                _ => return Err(self.unexpected(t, "`}`")),
+            }
+        }
+    }
+    /// Skips a word along with any brace groups directly following it
+    fn skip_node(&mut self) -> Result<(), ParseError> {
+        self.bump();
+        while self.peek().typ == TokenType::OpenBrace {
+            self.skip_group()?;
+        }
+        Ok(())
+    }
+    fn skip_group(&mut self) -> Result<(), ParseError> {
+        self.consume_braced_string().map(|_| ())
+    }
+    fn skip_unsupported(&mut self, t: Token<'a>) -> Result<(), ParseError> {
+        self.warn(t, format!("Skipping unsupported node `{}`", t.word));
+        self.bump();
+        // the node's name, if any
+        if self.peek().typ == TokenType::Word
+            && self.tokens.get(self.i + 1).map(|t| t.typ) == Some(TokenType::OpenBrace)
+        {
+            self.bump();
+        }
+        while self.peek().typ == TokenType::OpenBrace {
+            self.skip_group()?;
+        }
+        Ok(())
+    }
+
+    /// Runs `f` on the node starting at the current token. When recovering,
+    /// errors are recorded and the rest of the node is skipped.
+    fn recovering<T>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<T, ParseError>,
+    ) -> Result<Option<T>, ParseError> {
+        let start = self.i;
+        match f(self) {
+            Ok(v) => Ok(Some(v)),
+            Err(e) if self.recover => {
+                self.diagnostics.push(e.into());
+                self.synchronize(start);
+                Ok(None)
+            }
+            Err(e) => Err(e),
+        }
+    }
+    fn warn(&mut self, t: Token<'a>, message: String) {
+        self.diagnostics.push(Diagnostic {
+            severity: Severity::Warning,
+            span: t.span(),
+            message,
+        });
+    }
+    /// Warns about the value starting at token `start`
+    fn warn_since(&mut self, start: usize, message: String) {
+        let span = self.span_since(start);
+        self.diagnostics.push(Diagnostic {
+            severity: Severity::Warning,
+            span,
+            message,
+        });
+    }
+    /// Records `e` when recovering, otherwise returns it
+    fn report(&mut self, e: ParseError) -> Result<(), ParseError> {
+        if self.recover {
+            self.diagnostics.push(e.into());
+            Ok(())
+        } else {
+            Err(e)
+        }
+    }
+    /// Skips past the node starting at token `start`, stopping after its last
+    /// balanced brace group, before the `}` closing the enclosing list, or at a
+    /// top-level keyword starting a line. Keywords within brace groups, like in
+    /// code, only count when the file ends before the groups close. Keywords
+    /// and end of file unwind every enclosing list.
+    fn synchronize(&mut self, start: usize) {
+        self.i = start;
+        self.bump();
+        let mut depth = 0;
+        let mut fallback = None;
+        loop {
+            let t = self.peek();
+            match t.typ {
+                TokenType::Eof => {
+                    if let Some(i) = fallback {
+                        self.i = i;
                     }
+                    self.unwinding = true;
+                    return;
                 }
-                "user_data_type" => {
-                    self.i += 1;
-                    if self.tokens[self.i].typ == TokenType::OpenBrace {
-                        w.props.user_data_type = Some(self.consume_braced_string());
-                    } else {
-                        w.props.user_data_type = Some(self.tokens[self.i].word.to_string());
+                TokenType::OpenBrace => depth += 1,
+                TokenType::CloseBrace => {
+                    if depth == 0 {
+                        return;
                     }
-                }
-                "comment" => {
-                    self.i += 1;
-                    if self.tokens[self.i].typ == TokenType::OpenBrace {
-                        w.props.comment = Some(self.consume_braced_string());
-                    } else {
-                        w.props.comment = Some(self.tokens[self.i].word.to_string());
+                    depth -= 1;
+                    if depth == 0
+                        && self.tokens.get(self.i + 1).map(|t| t.typ) != Some(TokenType::OpenBrace)
+                    {
+                        self.bump();
+                        return;
                     }
                 }
-                "parent_properties" => {
-                    while self.tokens[self.i].typ != TokenType::CloseBrace {
-                        self.i += 1;
-                        w.props.parent_properties = Some(self.consume_parent_props());
+                TokenType::Word if SYNC_KEYWORDS.contains(&t.word) && self.at_line_start(t) => {
+                    if depth == 0 {
+                        self.unwinding = true;
+                        return;
                     }
+                    fallback.get_or_insert(self.i);
                 }
                 _ => (),
             }
+            self.bump();
         }
-        if self.tokens[self.i + 1].typ == TokenType::OpenBrace {
-            self.i += 1;
-            while self.tokens[self.i].typ != TokenType::CloseBrace {
-                self.i += 1;
-                while self.tokens[self.i].word.starts_with("Fl_")
-                    || self.tokens[self.i].word == "MenuItem"
-                    || self.tokens[self.i].word == "Submenu"
-                {
-                    let c = self.consume_widget();
-                    w.children.push(c);
-                    self.i += 1;
+    }
+    fn at_line_start(&self, t: Token<'a>) -> bool {
+        t.start == 0 || self.lexer.s.as_bytes().get(t.start - 1) == Some(&b'\n')
+    }
+    fn nested<T>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<T, ParseError>,
+    ) -> Result<T, ParseError> {
+        if self.nesting >= MAX_NESTING {
+            let t = self.peek();
+            return Err(self.error(t, "shallower nesting", "Nodes are nested too deeply".into()));
+        }
+        self.nesting += 1;
+        let ret = f(self);
+        self.nesting -= 1;
+        ret
+    }
+
+    fn peek(&self) -> Token<'a> {
+        match self.tokens.get(self.i) {
+            Some(t) => *t,
+            None => {
+                let end = self.lexer.s.len();
+                Token {
+                    typ: TokenType::Eof,
+                    word: "",
+                    start: end,
+                    end,
                 }
             }
         }
-        w
     }
-    fn consume_class(&mut self) -> Class {
-        let mut c = Class::default();
-        self.i += 1;
-        if self.tokens[self.i].typ == TokenType::OpenBrace {
-            self.i += 2;
-        }
-        c.name = self.tokens[self.i].word.to_string();
-        self.i += 1;
-        // handle props
-        while self.tokens[self.i].typ != TokenType::CloseBrace {
+    fn bump(&mut self) -> Token<'a> {
+        let t = self.peek();
+        if t.typ != TokenType::Eof {
             self.i += 1;
-            match self.tokens[self.i].word {
-                "open" => c.props.open = Some(true),
-                "protected" => c.props.visibility = Some(Visibility::PROTECTED),
-                "private" => c.props.visibility = Some(Visibility::PRIVATE),
-                "comment" => {
-                    self.i += 1;
-                    if self.tokens[self.i].typ == TokenType::OpenBrace {
-                        c.props.comment = Some(self.consume_braced_string());
-                    } else {
-                        c.props.comment = Some(self.tokens[self.i].word.to_string());
-                    }
-                }
-                _ => (),
-            }
         }
-        self.i += 1;
-        if self.tokens[self.i].typ == TokenType::OpenBrace {
-            while self.tokens[self.i].typ != TokenType::CloseBrace {
-                self.i += 1;
-                while self.tokens[self.i].word == "Function" {
-                    let f = self.consume_func();
-                    c.functions.push(f);
-                }
-                if self.tokens[self.i].word == "comment" {
-                    self.i += 1;
-                    if self.tokens[self.i].typ == TokenType::OpenBrace {
-                        c.props.comment = Some(self.consume_braced_string());
-                    } else {
-                        c.props.comment = Some(self.tokens[self.i].word.to_string());
-                    }
-                }
-            }
+        t
+    }
+    fn expect(&mut self, typ: TokenType, expected: &'static str) -> Result<Token<'a>, ParseError> {
+        let t = self.peek();
+        if t.typ != typ {
+            return Err(self.unexpected(t, expected));
         }
-        c
+        self.bump();
+        Ok(t)
     }
-    fn consume_comment(&mut self) -> Comment {
-        let mut c = Comment::default();
-        self.i += 1;
-        c.comment = self.consume_braced_string();
-        while self.tokens[self.i].typ != TokenType::Eof {
-            self.i += 1;
-            if self.tokens[self.i].typ == TokenType::CloseBrace {
-                break;
-            }
-            match self.tokens[self.i].word {
-                "in_source" => c.props.in_source = Some(true),
-                "in_header" => c.props.in_header = Some(true),
-                _ => (),
-            }
+    fn unexpected(&self, t: Token<'a>, expected: &'static str) -> ParseError {
+        let found = match t.typ {
+            TokenType::Eof => "end of file".to_string(),
+            _ => format!("`{}`", self.token_text(t)),
+        };
+        self.error(
+            t,
+            expected,
+            format!("Expected {}, found {}", expected, found),
+        )
+    }
+    /// Span from token `start` to the last consumed token
+    fn span_since(&self, start: usize) -> Span {
+        let first = self.tokens.get(start).map(Token::span).unwrap_or_default();
+        match self.tokens.get(self.i.wrapping_sub(1)) {
+            Some(last) if self.i > start => first.to(last.span()),
+            _ => first,
         }
-        c
     }
-    fn consume_decl(&mut self) -> Decl {
-        let mut d = Decl::default();
-        self.i += 1;
-        d.decl = self.consume_braced_string();
-        while self.tokens[self.i].typ != TokenType::Eof {
-            self.i += 1;
-            if self.tokens[self.i].typ == TokenType::CloseBrace {
-                break;
-            }
-            match self.tokens[self.i].word {
-                "private" => d.props.visibility = Visibility::PRIVATE,
-                "public" => d.props.visibility = Visibility::PUBLIC,
-                "global" => d.props.global = Some(true),
-                "local" => d.props.local = Some(true),
-                _ => (),
-            }
+    /// Span of the value of property `t`, or of `t` itself for flags
+    fn value_span(&self, start: usize, t: Token<'a>) -> Span {
+        if self.i > start {
+            self.span_since(start)
+        } else {
+            t.span()
         }
-        d
     }
-    fn consume_code(&mut self) -> String {
-        let s = self.consume_braced_string();
-        // skip last 2 braces
-        self.i += 1;
-        self.i += 1;
-        s
+    fn token_text(&self, t: Token<'a>) -> &'a str {
+        self.lexer.s.get(t.start..t.end).unwrap_or_default()
     }
+    fn error(&self, t: Token<'a>, expected: &'static str, message: String) -> ParseError {
+        ParseError {
+            token: self.token_text(t).to_string(),
+            typ: t.typ,
+            span: t.span(),
+            expected,
+            message,
+        }
+    }
+}
 
-    fn consume_braced_string(&mut self) -> String {
-        self.i += 1;
-        let mut t = self.tokens[self.i];
-        let start = t.start;
-        let mut openbrace = 1;
-        while t.typ != TokenType::Eof {
-            self.i += 1;
-            t = self.tokens[self.i];
-            if t.typ == TokenType::OpenBrace {
-                openbrace += 1;
-            }
-            if t.typ == TokenType::CloseBrace {
-                openbrace -= 1;
-            }
-            if openbrace == 0 {
-                break;
+/// Parses `s`, panicking on errors
+#[cfg(test)]
+pub(crate) fn parse_ok(s: &str) -> Ast {
+    Parser::new(Lexer::new(s)).try_parse().unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::span::SourceMap;
+
+    #[test]
+    fn raw_text_of_unescaped_values() {
+        let s = include_str!("../fl_tests/escapes.fl");
+        let a = parse_ok(s);
+        let map = SourceMap::new(s);
+        let d = a.decls().next().unwrap();
+        assert_eq!(d.decl, "#include <stdio.h>");
+        assert_eq!(map.text(d.decl_span), "{\\#include <stdio.h>}");
+        let f = a.functions().next().unwrap();
+        assert_eq!(f.name, "make_window()");
+        assert_eq!(map.text(f.name_span), "{make_window()}");
+        let but = &f.widgets().next().unwrap().children[0];
+        assert_eq!(but.props.label.as_deref(), Some("Open { brace"));
+        assert_eq!(map.text(but.props.spans["label"]), "{Open \\{ brace}");
+        assert_eq!(map.text(but.name_span), "but");
+    }
+
+    #[test]
+    fn raw_text_of_code_and_blocks() {
+        let s = "Function {} {} {\n  code {a \\} b;} {}\n  codeblock {if (x) \\{} {after {\\}}} {}\n}\ncomment {// \\#1} {}\n";
+        let a = parse_ok(s);
+        let map = SourceMap::new(s);
+        let f = a.functions().next().unwrap();
+        match &f.children[..] {
+            [Item::Code(c), Item::CodeBlock(b)] => {
+                assert_eq!(c.code, "a } b;");
+                assert_eq!(map.text(c.code_span), "{a \\} b;}");
+                assert_eq!(b.code, "if (x) {");
+                assert_eq!(map.text(b.code_span), "{if (x) \\{}");
+                assert_eq!(b.props.after.as_deref(), Some("}"));
+                assert_eq!(map.text(b.props.spans["after"]), "{\\}}");
             }
+            c => panic!("unexpected children {:?}", c),
         }
-        let end = self.tokens[self.i].end - 1;
-        self.lexer.s[start..end].to_string()
+        let c = a.comments().next().unwrap();
+        assert_eq!(c.comment, "// #1");
+        assert_eq!(map.text(c.comment_span), "{// \\#1}");
     }
-    fn consume_parent_props(&mut self) -> ParentProps {
-        let mut p = ParentProps::default();
-        while self.tokens[self.i].typ != TokenType::Eof {
-            self.i += 1;
-            if self.tokens[self.i].typ == TokenType::CloseBrace {
-                break;
+
+    #[test]
+    fn code_and_decl_blocks() {
+        let a = parse_ok(include_str!("../fl_tests/blocks.fl"));
+        let d = match &a.items[..] {
+            [Item::DeclBlock(d), Item::Class(_)] => d,
+            i => panic!("unexpected items {:?}", i),
+        };
+        assert_eq!(d.decl, "#ifdef HAVE_DEBUG");
+        assert_eq!(d.props.after.as_deref(), Some("#endif"));
+        assert_eq!(d.props.visibility, Some(Visibility::PUBLIC));
+        match &d.children[..] {
+            [Item::Decl(decl), Item::Function(f)] => {
+                assert_eq!(decl.decl, "int debug_level;");
+                assert_eq!(f.name, "debug_window()");
+                assert_eq!(f.widgets().count(), 1);
             }
-            match self.tokens[self.i].word {
-                "location" => {
-                    self.i += 1;
-                    p.location = Some(self.consume_braced_string());
-                },
-                _ => (),
+            c => panic!("unexpected children {:?}", c),
+        }
+
+        let panel = a.classes().next().unwrap();
+        let d = match &panel.members[..] {
+            [Item::DeclBlock(d)] => d,
+            m => panic!("unexpected members {:?}", m),
+        };
+        assert_eq!(d.decl, "#if 1");
+        let f = match &d.children[..] {
+            [Item::Function(f)] => f,
+            c => panic!("unexpected children {:?}", c),
+        };
+        let c = match &f.children[..] {
+            [Item::Code(first), Item::CodeBlock(c), Item::Code(last)] => {
+                assert_eq!(first.code, "int n = 0;");
+                assert_eq!(last.code, "return;");
+                c
+            }
+            c => panic!("unexpected children {:?}", c),
+        };
+        assert_eq!(c.code, "if (n == 0)");
+        assert_eq!(c.props.after.as_deref(), Some("/* done */"));
+        match &c.children[..] {
+            [Item::Code(code), Item::Widget(w)] => {
+                assert_eq!(code.code, "n++;");
+                assert_eq!(w.name, "win");
             }
+            c => panic!("unexpected children {:?}", c),
+        }
+    }
+
+    #[test]
+    fn nested_class_members() {
+        let (a, d) = parse_recovering(include_str!("../fl_tests/class.fl"));
+        assert!(d.is_empty(), "{:?}", d);
+        let c = a.classes().next().unwrap();
+        assert_eq!(c.members.len(), 8);
+        assert!(matches!(c.members.last(), Some(Item::Class(_))));
+        let nested = c.classes().next().unwrap();
+        assert_eq!(nested.name, "Settings");
+        assert_eq!(nested.props.visibility, Some(Visibility::PROTECTED));
+        assert_eq!(nested.decls().next().unwrap().decl, "int level;");
+        let f = nested.functions().next().unwrap();
+        assert_eq!(f.name, "reset()");
+        assert_eq!(f.code().next().unwrap().code, "level = 0;");
+    }
+
+    /// `levels` nested nodes opened by `open`, inside a function unless they are classes
+    fn nested_source(open: &str, levels: usize) -> String {
+        let mut s = String::new();
+        let class = open.starts_with("class");
+        if !class {
+            s.push_str("Function {} {} {\n");
+        }
+        for _ in 0..levels {
+            s.push_str(open);
+        }
+        for _ in 0..levels {
+            s.push_str("}\n");
+        }
+        if !class {
+            s.push_str("}\n");
         }
-        p
+        s
+    }
+
+    #[test]
+    fn nesting_limit_fits_in_a_small_stack() {
+        let t = std::thread::Builder::new()
+            .stack_size(2 << 20)
+            .spawn(|| {
+                for open in [
+                    "Fl_Group {} {} {\n",
+                    "codeblock {if (x)} {} {\n",
+                    "declblock {#if X} {} {\n",
+                    "class C {} {\n",
+                ] {
+                    let s = nested_source(open, MAX_NESTING);
+                    assert!(Parser::new(Lexer::new(&s)).try_parse().is_ok(), "{}", open);
+                    let s = nested_source(open, MAX_NESTING + 1);
+                    let e = Parser::new(Lexer::new(&s)).try_parse().unwrap_err();
+                    assert_eq!(e.message, "Nodes are nested too deeply");
+                    let (_, d) = Parser::new(Lexer::new(&s)).parse_with_diagnostics();
+                    assert_eq!(d[0].message, "Nodes are nested too deeply");
+                }
+            })
+            .unwrap();
+        t.join().unwrap();
+    }
+
+    fn parse_recovering(s: &str) -> (Ast, Vec<Diagnostic>) {
+        Parser::new(Lexer::new(s)).parse_with_diagnostics()
+    }
+
+    fn errors<'s>(s: &'s str, d: &[Diagnostic]) -> Vec<(&'s str, String)> {
+        d.iter()
+            .filter(|d| d.severity == Severity::Error)
+            .map(|d| (&s[d.span.start..d.span.end], d.message.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn recovers_from_bad_prop_in_nested_widget() {
+        let s = "Function {a()} {open\n} {\n  Fl_Window {} {open\n  } {\n    Fl_Button b {\n      xywh 1 2 3 4\n    }\n    Fl_Box c {\n      label ok\n    }\n  }\n}\nFunction {b()} {open\n} {\n  code {x;} {}\n}\n";
+        let (a, d) = parse_recovering(s);
+        assert_eq!(d.len(), 1);
+        assert_eq!(
+            errors(s, &d),
+            vec![("1", "Expected `{`, found `1`".to_string())]
+        );
+        let f: Vec<_> = a.functions().collect();
+        assert_eq!(f.len(), 2);
+        // the broken button is dropped, its sibling is kept
+        let win = f[0].widgets().next().unwrap();
+        let names: Vec<_> = win.children.iter().map(|w| w.name.as_str()).collect();
+        assert_eq!(names, ["c"]);
+        assert_eq!(f[1].name, "b()");
+        assert_eq!(f[1].code().next().unwrap().code, "x;");
+    }
+
+    #[test]
+    fn resynchronizes_at_next_function() {
+        // a missing `}` leaves `a()` open until the end of file, the error drops
+        // it and parsing resumes at the `Function` keyword starting a line
+        let s = "Function {a()} {open\n} {\n  Fl_Window {} {open\n  } {\n    Fl_Button b {\n      xywh {1 2 3}\n  }\n}\nFunction {b()} {open\n} {\n  code {x;} {}\n}\n";
+        let (a, d) = parse_recovering(s);
+        assert_eq!(
+            errors(s, &d),
+            vec![
+                ("{1 2 3}", "Expected 4 values for `xywh`, found 3".to_string()),
+                ("", "Expected `}`, found end of file".to_string()),
+            ]
+        );
+        let f: Vec<_> = a.functions().collect();
+        assert_eq!(f.len(), 1);
+        assert_eq!(f[0].name, "b()");
+        assert_eq!(f[0].code().next().unwrap().code, "x;");
+    }
+
+    #[test]
+    fn ignores_keywords_inside_skipped_groups() {
+        let s = "Function {a()} {open\n} {\n  codeblock {if (x)} {open after\n  } {\n    code {x = 1;\nFunction f;\n} {}\n  }\n  code {y = 2;} {}\n}\nFunction {b()} {open\n} {\n}\n";
+        let (a, d) = parse_recovering(s);
+        assert_eq!(
+            errors(s, &d),
+            vec![("}", "Expected code, found `}`".to_string())]
+        );
+        assert_eq!(d.len(), 1);
+        let f: Vec<_> = a.functions().collect();
+        assert_eq!(f.len(), 2);
+        assert_eq!(f[0].name, "a()");
+        let code: Vec<_> = f[0].code().map(|c| c.code.as_str()).collect();
+        assert_eq!(code, ["y = 2;"]);
+        assert_eq!(f[1].name, "b()");
+    }
+
+    #[test]
+    fn keeps_partial_tree_of_truncated_input() {
+        let s = "decl {int x;} {private local\n}\nFunction {a()} {open\n} {\n  Fl_Window w {open\n    xywh {0 0 10 10}\n  } {\n    Fl_Button b {\n      label {trunc";
+        let (a, d) = parse_recovering(s);
+        assert_eq!(
+            errors(s, &d),
+            vec![("", "Expected `}`, found end of file".to_string())]
+        );
+        assert_eq!(d[0].span, Span::new(s.len(), s.len()));
+        assert_eq!(a.decls().next().unwrap().decl, "int x;");
+        let f = a.functions().next().unwrap();
+        assert_eq!(f.name, "a()");
+        let w = f.widgets().next().unwrap();
+        assert_eq!(w.name, "w");
+        assert_eq!(w.props.rect.map(|r| r.w), Some(10));
+        assert!(w.children.is_empty());
+    }
+
+    #[test]
+    fn skips_stray_close_brace_at_top_level() {
+        let s = "}\ndecl {int x;} {public\n}\n}\nFunction {f()} {} {}\n";
+        let (a, d) = parse_recovering(s);
+        let stray = "Expected top-level item, found `}`".to_string();
+        assert_eq!(errors(s, &d), vec![("}", stray.clone()), ("}", stray)]);
+        assert_eq!(d[1].span.start, s.rfind("}\nFunction").unwrap());
+        assert_eq!(a.items.len(), 2);
+        assert_eq!(a.decls().next().unwrap().props.visibility, Visibility::PUBLIC);
+        assert_eq!(a.functions().next().unwrap().name, "f()");
+        assert!(Parser::new(Lexer::new(s)).try_parse().is_err());
     }
 }
@@ -0,0 +1,260 @@
+use crate::error::ValueError;
+use std::fmt;
+use std::str::FromStr;
+
+/// Modifier flags of a shortcut, in FLTK's encoding
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Modifiers(pub u32);
+
+impl Modifiers {
+    pub const NONE: Modifiers = Modifiers(0);
+    pub const SHIFT: Modifiers = Modifiers(0x10000);
+    pub const CAPS_LOCK: Modifiers = Modifiers(0x20000);
+    pub const CTRL: Modifiers = Modifiers(0x40000);
+    pub const ALT: Modifiers = Modifiers(0x80000);
+    pub const NUM_LOCK: Modifiers = Modifiers(0x100000);
+    pub const META: Modifiers = Modifiers(0x400000);
+    pub const SCROLL_LOCK: Modifiers = Modifiers(0x800000);
+    /// `FL_COMMAND`, which is `FL_META` on macOS and `FL_CTRL` elsewhere
+    pub const COMMAND: Modifiers = Modifiers::CTRL;
+    pub const MASK: Modifiers = Modifiers(0xffff0000);
+
+    pub fn contains(self, other: Modifiers) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl std::ops::BitOr for Modifiers {
+    type Output = Modifiers;
+    fn bitor(self, rhs: Modifiers) -> Modifiers {
+        Modifiers(self.0 | rhs.0)
+    }
+}
+
+/// Modifier names in display order
+const MODIFIER_NAMES: &[(&str, Modifiers)] = &[
+    ("Ctrl", Modifiers::CTRL),
+    ("Alt", Modifiers::ALT),
+    ("Meta", Modifiers::META),
+    ("Shift", Modifiers::SHIFT),
+    ("CapsLock", Modifiers::CAPS_LOCK),
+    ("NumLock", Modifiers::NUM_LOCK),
+    ("ScrollLock", Modifiers::SCROLL_LOCK),
+];
+
+/// Names of FLTK's special keys, from `FL_BackSpace` to `FL_Delete`
+const KEY_NAMES: &[(&str, u32)] = &[
+    ("Space", 0x20),
+    ("BackSpace", 0xff08),
+    ("Tab", 0xff09),
+    ("Enter", 0xff0d),
+    ("Pause", 0xff13),
+    ("Scroll_Lock", 0xff14),
+    ("Escape", 0xff1b),
+    ("Home", 0xff50),
+    ("Left", 0xff51),
+    ("Up", 0xff52),
+    ("Right", 0xff53),
+    ("Down", 0xff54),
+    ("Page_Up", 0xff55),
+    ("Page_Down", 0xff56),
+    ("End", 0xff57),
+    ("Print", 0xff61),
+    ("Insert", 0xff63),
+    ("Menu", 0xff67),
+    ("Help", 0xff68),
+    ("Num_Lock", 0xff7f),
+    ("KP_Enter", 0xff8d),
+    ("Delete", 0xffff),
+];
+
+/// `FL_F`, function key `n` is `FL_F + n`
+const FN_KEY: u32 = 0xffbd;
+
+/// A keyboard shortcut, FLTK encodes it as the key ORed with the modifiers
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Shortcut {
+    /// Character or FLTK key code, letters are lowercase
+    pub key: u32,
+    pub modifiers: Modifiers,
+}
+
+impl Shortcut {
+    pub fn from_fltk(v: u32) -> Shortcut {
+        Shortcut {
+            key: v & 0xffff,
+            modifiers: Modifiers(v & Modifiers::MASK.0),
+        }
+    }
+
+    pub fn to_fltk(self) -> u32 {
+        self.key | self.modifiers.0
+    }
+
+    fn parse_key(s: &str) -> Option<u32> {
+        if let Some((_, v)) = KEY_NAMES.iter().find(|(n, _)| n.eq_ignore_ascii_case(s)) {
+            return Some(*v);
+        }
+        if let Some(n) = s.strip_prefix(['F', 'f']).and_then(|n| n.parse::<u32>().ok()) {
+            if (1..=35).contains(&n) {
+                return Some(FN_KEY + n);
+            }
+        }
+        if let Some(hex) = s.strip_prefix("0x") {
+            return u32::from_str_radix(hex, 16).ok();
+        }
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Some(c.to_ascii_lowercase() as u32),
+            _ => None,
+        }
+    }
+}
+
+/// Accepts FLTK's integer encoding, hexadecimal as FLUID writes it or
+/// decimal, and the notation produced by `Display`, like `Ctrl+Shift+Q`.
+/// `Cmd` and `Command` are read as `Ctrl`, since `Modifiers::COMMAND` is
+/// `FL_COMMAND` as FLUID stores it outside of macOS.
+impl FromStr for Shortcut {
+    type Err = ValueError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ValueError::new(format!("Invalid shortcut `{}`", s));
+        if let Some(hex) = s.strip_prefix("0x").filter(|h| !h.contains('+')) {
+            return u32::from_str_radix(hex, 16)
+                .map(Shortcut::from_fltk)
+                .map_err(|_| invalid());
+        }
+        if s.len() > 1 && s.bytes().all(|b| b.is_ascii_digit()) {
+            return s.parse().map(Shortcut::from_fltk).map_err(|_| invalid());
+        }
+        // a trailing `+` is the plus key, as in `Ctrl++`
+        let (mods, key) = match s.strip_suffix("++") {
+            Some(mods) => (mods, "+"),
+            None if s == "+" => ("", "+"),
+            None => s.rsplit_once('+').unwrap_or(("", s)),
+        };
+        let mut sc = Shortcut {
+            key: Self::parse_key(key).ok_or_else(invalid)?,
+            ..Default::default()
+        };
+        for m in mods.split('+').filter(|m| !m.is_empty()) {
+            sc.modifiers = sc.modifiers
+                | match m.to_ascii_lowercase().as_str() {
+                    "ctrl" | "control" => Modifiers::CTRL,
+                    "alt" => Modifiers::ALT,
+                    "meta" => Modifiers::META,
+                    "shift" => Modifiers::SHIFT,
+                    "cmd" | "command" => Modifiers::COMMAND,
+                    "capslock" => Modifiers::CAPS_LOCK,
+                    "numlock" => Modifiers::NUM_LOCK,
+                    "scrolllock" => Modifiers::SCROLL_LOCK,
+                    // other bits, as `Display` writes them
+                    m => match m.strip_prefix("0x").map(|h| u32::from_str_radix(h, 16)) {
+                        Some(Ok(v)) if v & !Modifiers::MASK.0 == 0 => Modifiers(v),
+                        _ => return Err(invalid()),
+                    },
+                };
+        }
+        Ok(sc)
+    }
+}
+
+impl fmt::Display for Shortcut {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (name, m) in MODIFIER_NAMES {
+            if self.modifiers.contains(*m) {
+                write!(f, "{}+", name)?;
+            }
+        }
+        let known = MODIFIER_NAMES.iter().fold(0, |acc, (_, m)| acc | m.0);
+        if self.modifiers.0 & !known != 0 {
+            write!(f, "{:#x}+", self.modifiers.0 & !known)?;
+        }
+        if let Some((name, _)) = KEY_NAMES.iter().find(|(_, k)| *k == self.key) {
+            return f.write_str(name);
+        }
+        match self.key {
+            k if k > FN_KEY && k <= FN_KEY + 35 => write!(f, "F{}", k - FN_KEY),
+            k => match std::char::from_u32(k) {
+                // uppercase keys are written in hex, uppercase letters stand for lowercase keys
+                Some(c) if c.is_ascii_graphic() && !c.is_ascii_uppercase() => {
+                    write!(f, "{}", c.to_ascii_uppercase())
+                }
+                _ => write!(f, "{:#x}", k),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(s: &str, v: u32) {
+        let sc: Shortcut = s.parse().unwrap();
+        assert_eq!(sc.to_fltk(), v, "{}", s);
+        assert_eq!(sc.to_string(), s);
+        assert_eq!(Shortcut::from_fltk(v), sc);
+    }
+
+    #[test]
+    fn common_shortcuts() {
+        round_trip("Ctrl+Q", 0x40071);
+        round_trip("Ctrl+Shift+S", 0x50073);
+        round_trip("Escape", 0xff1b);
+        round_trip("Left", 0xff51);
+        round_trip("Up", 0xff52);
+        round_trip("Right", 0xff53);
+        round_trip("Alt+Down", 0x8ff54);
+        round_trip("Ctrl++", 0x4002b);
+        round_trip("+", 0x2b);
+        for n in 1..=12 {
+            round_trip(&format!("F{}", n), 0xffbd + n);
+            round_trip(&format!("Shift+F{}", n), 0x1ffbd + n);
+        }
+    }
+
+    #[test]
+    fn fluid_encodings() {
+        assert_eq!("0x40071".parse::<Shortcut>().unwrap().to_string(), "Ctrl+Q");
+        assert_eq!("262257".parse::<Shortcut>().unwrap().to_fltk(), 0x40071);
+        assert_eq!("ctrl+q".parse::<Shortcut>().unwrap().to_fltk(), 0x40071);
+        assert!("Hyper+Q".parse::<Shortcut>().is_err());
+        assert!("Ctrl+Query".parse::<Shortcut>().is_err());
+        assert!("Ctrl+0x1+Q".parse::<Shortcut>().is_err());
+    }
+
+    #[test]
+    fn command_is_ctrl() {
+        assert_eq!("Cmd+Q".parse::<Shortcut>().unwrap().to_string(), "Ctrl+Q");
+        assert_eq!("Command+Q".parse::<Shortcut>().unwrap().modifiers, Modifiers::CTRL);
+    }
+
+    #[test]
+    fn lock_and_unknown_modifiers() {
+        let sc = Shortcut::from_fltk(0xa006f);
+        assert_eq!(sc.to_string(), "Alt+CapsLock+O");
+        assert_eq!(sc.to_string().parse::<Shortcut>().unwrap().to_fltk(), 0xa006f);
+        round_trip("NumLock+ScrollLock+A", 0x900061);
+        round_trip("Ctrl+0x1000000+A", 0x1040061);
+        round_trip("0x41", 0x41);
+    }
+
+    #[test]
+    fn every_encoding_round_trips() {
+        let keys = (0..0x200).chain(0xff00..=0xffff);
+        for key in keys {
+            for bits in 0..0x100 {
+                let mods = MODIFIER_NAMES
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, _)| bits & (1 << i) != 0)
+                    .fold(0, |acc, (_, (_, m))| acc | m.0);
+                let mods = if bits & 0x80 != 0 { mods | 0x1000000 } else { mods };
+                let v = key | mods;
+                let s = Shortcut::from_fltk(v).to_string();
+                assert_eq!(s.parse::<Shortcut>().map(Shortcut::to_fltk), Ok(v), "{}", s);
+            }
+        }
+    }
+}
@@ -0,0 +1,383 @@
+use crate::ast::Function;
+use crate::error::ValueError;
+use std::str::FromStr;
+
+/// Parameter syntax used by a signature
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum SignatureStyle {
+    /// `name: Type`
+    Rust,
+    /// `Type name`
+    #[default]
+    Cpp,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Param {
+    /// Empty for unnamed C++ parameters
+    pub name: String,
+    pub typ: String,
+    pub default: Option<String>,
+}
+
+/// A function name split into its parts, e.g. `make_window(int w, int h = 100)`
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Signature {
+    pub name: String,
+    pub params: Vec<Param>,
+    pub return_type: Option<String>,
+    /// Text following the parameter list, like `const` or a constructor's initializer list
+    pub suffix: Option<String>,
+    /// `Cpp` when there are no parameters to tell from
+    pub style: SignatureStyle,
+}
+
+impl Function {
+    /// Parses the function name, taking the return type from
+    /// the `return_type` property unless the name has a Rust-style `-> T`
+    pub fn signature(&self) -> Result<Signature, ValueError> {
+        let mut s: Signature = self.name.parse()?;
+        if s.return_type.is_none() {
+            s.return_type = self.props.return_type.clone();
+        }
+        Ok(s)
+    }
+}
+
+impl Signature {
+    /// The signature FLUID generates for a function without a name,
+    /// `int main(int argc, char **argv)`
+    pub fn main() -> Signature {
+        Signature {
+            name: "main".to_string(),
+            params: vec![
+                Param {
+                    name: "argc".to_string(),
+                    typ: "int".to_string(),
+                    default: None,
+                },
+                Param {
+                    name: "argv".to_string(),
+                    typ: "char **".to_string(),
+                    default: None,
+                },
+            ],
+            return_type: Some("int".to_string()),
+            ..Default::default()
+        }
+    }
+}
+
+/// An empty name is FLUID's `main`, see `Signature::main`
+impl FromStr for Signature {
+    type Err = ValueError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.trim().is_empty() {
+            return Ok(Signature::main());
+        }
+        let open = s
+            .find('(')
+            .ok_or_else(|| ValueError::new(format!("Missing parameter list in `{}`", s)))?;
+        let mut depth = 0;
+        let mut close = None;
+        for (i, c) in s[open..].char_indices() {
+            match c {
+                '(' => depth += 1,
+                ')' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        close = Some(open + i);
+                        break;
+                    }
+                }
+                _ => (),
+            }
+        }
+        let close =
+            close.ok_or_else(|| ValueError::new(format!("Unbalanced parentheses in `{}`", s)))?;
+        let mut sig = Signature {
+            name: s[..open].trim().to_string(),
+            ..Default::default()
+        };
+        if sig.name.is_empty() {
+            return Err(ValueError::new(format!("Missing function name in `{}`", s)));
+        }
+        let params = s[open + 1..close].trim();
+        if !params.is_empty() && params != "void" {
+            for p in split_top_level(params, ',') {
+                let (p, style) = parse_param(p)?;
+                if style == SignatureStyle::Rust {
+                    sig.style = SignatureStyle::Rust;
+                }
+                sig.params.push(p);
+            }
+        }
+        let suffix = s[close + 1..].trim();
+        if let Some(ret) = suffix.strip_prefix("->") {
+            sig.return_type = Some(ret.trim().to_string());
+            sig.style = SignatureStyle::Rust;
+        } else if !suffix.is_empty() {
+            sig.suffix = Some(suffix.to_string());
+        }
+        Ok(sig)
+    }
+}
+
+/// Words which are types by themselves, so never the name of an unnamed C++ parameter
+const TYPE_WORDS: &[&str] = &[
+    "int", "char", "short", "long", "float", "double", "bool", "void", "unsigned", "signed",
+    "auto", "const",
+];
+
+fn parse_param(s: &str) -> Result<(Param, SignatureStyle), ValueError> {
+    let mut p = Param::default();
+    let decl = match top_level_position(s, |s, i| {
+        s[i..].starts_with('=')
+            && !s[i + 1..].starts_with('=')
+            && !s[..i].ends_with(['=', '!', '<', '>'])
+    }) {
+        Some(i) => {
+            p.default = Some(s[i + 1..].trim().to_string());
+            s[..i].trim()
+        }
+        None => s.trim(),
+    };
+    if decl.is_empty() {
+        return Err(ValueError::new(format!("Empty parameter in `{}`", s.trim())));
+    }
+    // a lone `:`, as opposed to the `::` of paths, separates a Rust name from its type
+    if let Some(i) = top_level_position(decl, |s, i| {
+        s[i..].starts_with(':') && !s[i + 1..].starts_with(':') && !s[..i].ends_with(':')
+    }) {
+        let name = decl[..i].trim();
+        p.name = name.strip_prefix("mut ").unwrap_or(name).trim().to_string();
+        p.typ = decl[i + 1..].trim().to_string();
+        return Ok((p, SignatureStyle::Rust));
+    }
+    // Rust receivers, `self: &Self` written as `&self`
+    if let Some(typ) = receiver_type(decl) {
+        p.name = "self".to_string();
+        p.typ = typ;
+        return Ok((p, SignatureStyle::Rust));
+    }
+    if decl == "..." {
+        p.typ = decl.to_string();
+        return Ok((p, SignatureStyle::Cpp));
+    }
+    // function pointers, `void (*cb)(int)`
+    if let Some(i) = decl.find("(*") {
+        if let Some(len) = decl[i + 2..].find(')') {
+            p.name = decl[i + 2..i + 2 + len].trim().to_string();
+            p.typ = format!("{}(*){}", &decl[..i], &decl[i + 3 + len..]);
+            return Ok((p, SignatureStyle::Cpp));
+        }
+    }
+    let (decl, array) = match decl.find('[') {
+        Some(i) => (decl[..i].trim_end(), &decl[i..]),
+        None => (decl, ""),
+    };
+    let name_start = decl
+        .rfind(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .map_or(0, |i| i + 1);
+    let (typ, name) = decl.split_at(name_start);
+    let typ = typ.trim_end();
+    if typ.is_empty() || typ.ends_with("::") || TYPE_WORDS.contains(&name) {
+        p.typ = format!("{}{}", decl, array);
+    } else {
+        p.name = name.to_string();
+        p.typ = format!("{}{}", typ, array);
+    }
+    Ok((p, SignatureStyle::Cpp))
+}
+
+/// Type of a Rust receiver like `self`, `&mut self` or `&'a self`
+fn receiver_type(decl: &str) -> Option<String> {
+    let (reference, rest) = match decl.strip_prefix('&') {
+        Some(rest) => ("&", rest.trim_start()),
+        None => ("", decl),
+    };
+    let (lifetime, rest) = match rest.strip_prefix('\'') {
+        Some(_) if !reference.is_empty() => {
+            let (lifetime, rest) = rest.split_once(char::is_whitespace)?;
+            (format!("{} ", lifetime), rest.trim_start())
+        }
+        _ => (String::new(), rest),
+    };
+    let (mutable, rest) = match rest.strip_prefix("mut") {
+        Some(rest) if rest.starts_with(char::is_whitespace) => ("mut ", rest.trim_start()),
+        _ => ("", rest),
+    };
+    if rest != "self" {
+        return None;
+    }
+    // the `mut` of a receiver taken by value is a binding mode, not part of the type
+    let mutable = if reference.is_empty() { "" } else { mutable };
+    Some(format!("{}{}{}Self", reference, lifetime, mutable))
+}
+
+/// Byte offset of the first position outside of brackets and quotes where `pred` holds
+fn top_level_position(s: &str, pred: impl Fn(&str, usize) -> bool) -> Option<usize> {
+    let mut depth = 0i32;
+    let mut quote = None;
+    let mut escaped = false;
+    for (i, c) in s.char_indices() {
+        if let Some(q) = quote {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                c if c == q => quote = None,
+                _ => (),
+            }
+            continue;
+        }
+        match c {
+            '"' | '\'' => quote = Some(c),
+            '(' | '[' | '{' | '<' => depth += 1,
+            // the `>` of a Rust `->` closes nothing
+            '>' if s[..i].ends_with('-') => (),
+            ')' | ']' | '}' | '>' => depth -= 1,
+            _ if depth == 0 && pred(s, i) => return Some(i),
+            _ => (),
+        }
+    }
+    None
+}
+
+fn split_top_level(s: &str, sep: char) -> Vec<&str> {
+    let mut parts = vec![];
+    let mut rest = s;
+    while let Some(i) = top_level_position(rest, |s, i| s[i..].starts_with(sep)) {
+        parts.push(&rest[..i]);
+        rest = &rest[i + sep.len_utf8()..];
+    }
+    parts.push(rest);
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_ok;
+
+    fn param(name: &str, typ: &str, default: Option<&str>) -> Param {
+        Param {
+            name: name.to_string(),
+            typ: typ.to_string(),
+            default: default.map(str::to_string),
+        }
+    }
+
+    fn functions(s: &str) -> Vec<Signature> {
+        let a = parse_ok(s);
+        let mut sigs: Vec<_> = a.functions().map(|f| f.signature().unwrap()).collect();
+        for c in a.classes() {
+            sigs.extend(c.functions().map(|f| f.signature().unwrap()));
+        }
+        sigs
+    }
+
+    #[test]
+    fn fixture_signatures() {
+        let sigs = functions(include_str!("../fl_tests/funcs.fl"));
+        assert_eq!(sigs[0].name, "make_window1");
+        assert!(sigs[0].params.is_empty());
+        assert_eq!(sigs[0].return_type.as_deref(), Some("Button"));
+        assert_eq!(sigs[0].style, SignatureStyle::Cpp);
+        assert_eq!(
+            sigs[1].params,
+            vec![param("x", "i32", None), param("y", "i32", None)]
+        );
+        assert_eq!(sigs[1].style, SignatureStyle::Rust);
+
+        let sigs = functions(include_str!("../fl_tests/class.fl"));
+        assert_eq!(sigs[0].name, "MyWin");
+        assert_eq!(
+            sigs[0].params,
+            vec![
+                param("w", "int", None),
+                param("h", "int", None),
+                param("l", "const char *", Some("0")),
+            ]
+        );
+        assert_eq!(sigs[0].suffix.as_deref(), Some(": Fl_Double_Window(w, h, l)"));
+        assert_eq!(sigs[1].name, "increment");
+        assert_eq!(sigs[1].return_type.as_deref(), Some("void"));
+    }
+
+    #[test]
+    fn unnamed_function_is_main() {
+        let sigs = functions("Function {} {open\n} {\n}\n");
+        assert_eq!(sigs, vec![Signature::main()]);
+        assert_eq!(
+            sigs[0].params,
+            vec![param("argc", "int", None), param("argv", "char **", None)]
+        );
+        assert_eq!(sigs[0].return_type.as_deref(), Some("int"));
+    }
+
+    #[test]
+    fn rust_receivers() {
+        let s: Signature = "value(&self) -> i32".parse().unwrap();
+        assert_eq!(s.params, vec![param("self", "&Self", None)]);
+        assert_eq!(s.style, SignatureStyle::Rust);
+        assert_eq!(s.return_type.as_deref(), Some("i32"));
+        let s: Signature = "set(&mut self, v: i32)".parse().unwrap();
+        assert_eq!(
+            s.params,
+            vec![param("self", "&mut Self", None), param("v", "i32", None)]
+        );
+        let s: Signature = "take(mut self)".parse().unwrap();
+        assert_eq!(s.params, vec![param("self", "Self", None)]);
+        let s: Signature = "get(&'a self)".parse().unwrap();
+        assert_eq!(s.params, vec![param("self", "&'a Self", None)]);
+    }
+
+    #[test]
+    fn cpp_parameters() {
+        let s: Signature = "set_callback(void (*cb)(int), void *data)".parse().unwrap();
+        assert_eq!(
+            s.params,
+            vec![param("cb", "void (*)(int)", None), param("data", "void *", None)]
+        );
+        let s: Signature = "f(int, char buf[16], unsigned long, ...)".parse().unwrap();
+        assert_eq!(
+            s.params,
+            vec![
+                param("", "int", None),
+                param("buf", "char[16]", None),
+                param("", "unsigned long", None),
+                param("", "...", None),
+            ]
+        );
+        let s: Signature = "f(void) const".parse().unwrap();
+        assert!(s.params.is_empty());
+        assert_eq!(s.suffix.as_deref(), Some("const"));
+    }
+
+    #[test]
+    fn templated_defaults() {
+        let s: Signature = "f(std::map<int, int> m = std::map<int, int>(), bool b = 1 >= 2)"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            s.params,
+            vec![
+                param("m", "std::map<int, int>", Some("std::map<int, int>()")),
+                param("b", "bool", Some("1 >= 2")),
+            ]
+        );
+        let s: Signature = "g(opts: Vec<(i32, i32)>, s: &str)".parse().unwrap();
+        assert_eq!(
+            s.params,
+            vec![param("opts", "Vec<(i32, i32)>", None), param("s", "&str", None)]
+        );
+    }
+
+    #[test]
+    fn malformed_signatures() {
+        assert!("make_window".parse::<Signature>().is_err());
+        assert!("f(int x".parse::<Signature>().is_err());
+        assert!("(int x)".parse::<Signature>().is_err());
+        assert!("f(int x,)".parse::<Signature>().is_err());
+    }
+}
@@ -0,0 +1,152 @@
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+
+    /// Returns a span covering both `self` and `other`
+    pub fn to(self, other: Span) -> Span {
+        Span {
+            start: self.start.min(other.start),
+            end: self.end.max(other.end),
+        }
+    }
+}
+
+/// A 1-based line and column, the column counted in characters
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl std::fmt::Display for Position {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// Converts byte offsets of a source file into line/column positions
+#[derive(Debug, Clone)]
+pub struct SourceMap<'a> {
+    source: &'a str,
+    line_starts: Vec<usize>,
+}
+
+impl<'a> SourceMap<'a> {
+    pub fn new(source: &'a str) -> Self {
+        let mut line_starts = vec![0];
+        for (i, b) in source.bytes().enumerate() {
+            if b == b'\n' {
+                line_starts.push(i + 1);
+            }
+        }
+        Self {
+            source,
+            line_starts,
+        }
+    }
+
+    pub fn position(&self, offset: usize) -> Position {
+        let offset = offset.min(self.source.len());
+        let line = match self.line_starts.binary_search(&offset) {
+            Ok(i) => i,
+            Err(i) => i - 1,
+        };
+        let start = self.line_starts[line];
+        let column = match self.source.get(start..offset) {
+            Some(s) => s.chars().count(),
+            None => offset - start,
+        };
+        Position {
+            line: line + 1,
+            column: column + 1,
+        }
+    }
+
+    /// Returns the start and end positions of `span`
+    pub fn span_position(&self, span: Span) -> (Position, Position) {
+        (self.position(span.start), self.position(span.end))
+    }
+
+    /// Returns the source text covered by `span`
+    pub fn text(&self, span: Span) -> &'a str {
+        self.source.get(span.start..span.end).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pos(line: usize, column: usize) -> Position {
+        Position { line, column }
+    }
+
+    #[test]
+    fn first_line() {
+        let m = SourceMap::new("version 1.0400\nheader_name {.h}\n");
+        assert_eq!(m.position(0), pos(1, 1));
+        assert_eq!(m.position(8), pos(1, 9));
+        assert_eq!(m.position(14), pos(1, 15));
+        assert_eq!(m.position(15), pos(2, 1));
+        assert_eq!(m.position(27), pos(2, 13));
+        assert_eq!(pos(2, 13).to_string(), "2:13");
+    }
+
+    #[test]
+    fn crlf_line_endings() {
+        let m = SourceMap::new("Function {} {open\r\n} {\r\n}\r\n");
+        // the `\r` is the last character of its line
+        assert_eq!(m.position(17), pos(1, 18));
+        assert_eq!(m.position(18), pos(1, 19));
+        assert_eq!(m.position(19), pos(2, 1));
+        assert_eq!(m.position(21), pos(2, 3));
+        assert_eq!(m.position(24), pos(3, 1));
+    }
+
+    #[test]
+    fn multibyte_columns() {
+        let s = "label {Größe €}\ntooltip {é}";
+        let m = SourceMap::new(s);
+        let euro = s.find('€').unwrap();
+        assert_eq!(euro, 15);
+        assert_eq!(m.position(euro), pos(1, 14));
+        assert_eq!(m.position(euro + '€'.len_utf8()), pos(1, 15));
+        let e = s.find('é').unwrap();
+        assert_eq!(m.position(e), pos(2, 10));
+        assert_eq!(m.position(e + 2), pos(2, 11));
+    }
+
+    #[test]
+    fn offsets_at_and_past_eof() {
+        let s = "Function {} {}\n";
+        let m = SourceMap::new(s);
+        assert_eq!(m.position(s.len()), pos(2, 1));
+        assert_eq!(m.position(s.len() + 10), pos(2, 1));
+        let m = SourceMap::new("decl {int x;}");
+        assert_eq!(m.position(13), pos(1, 14));
+        assert_eq!(m.position(100), pos(1, 14));
+        let m = SourceMap::new("");
+        assert_eq!(m.position(0), pos(1, 1));
+    }
+
+    #[test]
+    fn spans() {
+        let s = "comment {a\nb} in_source\n";
+        let m = SourceMap::new(s);
+        let a = Span::new(8, 13);
+        assert_eq!(m.text(a), "{a\nb}");
+        assert_eq!(m.span_position(a), (pos(1, 9), pos(2, 3)));
+        let b = Span::new(14, 23);
+        assert_eq!(m.text(b), "in_source");
+        assert_eq!(a.to(b), Span::new(8, 23));
+        assert_eq!(b.to(a), Span::new(8, 23));
+        assert_eq!(m.text(Span::new(20, 40)), "");
+    }
+}
@@ -0,0 +1,765 @@
+use crate::error::ValueError;
+use std::fmt;
+use std::str::FromStr;
+
+/// Box and frame types in the order of FLTK's `Fl_Boxtype`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum BoxType {
+    NoBox,
+    FlatBox,
+    UpBox,
+    DownBox,
+    UpFrame,
+    DownFrame,
+    ThinUpBox,
+    ThinDownBox,
+    ThinUpFrame,
+    ThinDownFrame,
+    EngravedBox,
+    EmbossedBox,
+    EngravedFrame,
+    EmbossedFrame,
+    BorderBox,
+    ShadowBox,
+    BorderFrame,
+    ShadowFrame,
+    RoundedBox,
+    RShadowBox,
+    RoundedFrame,
+    RFlatBox,
+    RoundUpBox,
+    RoundDownBox,
+    DiamondUpBox,
+    DiamondDownBox,
+    OvalBox,
+    OShadowBox,
+    OvalFrame,
+    OFlatBox,
+    PlasticUpBox,
+    PlasticDownBox,
+    PlasticUpFrame,
+    PlasticDownFrame,
+    PlasticThinUpBox,
+    PlasticThinDownBox,
+    PlasticRoundUpBox,
+    PlasticRoundDownBox,
+    GtkUpBox,
+    GtkDownBox,
+    GtkUpFrame,
+    GtkDownFrame,
+    GtkThinUpBox,
+    GtkThinDownBox,
+    GtkThinUpFrame,
+    GtkThinDownFrame,
+    GtkRoundUpBox,
+    GtkRoundDownBox,
+    GleamUpBox,
+    GleamDownBox,
+    GleamUpFrame,
+    GleamDownFrame,
+    GleamThinUpBox,
+    GleamThinDownBox,
+    GleamRoundUpBox,
+    GleamRoundDownBox,
+    OxyUpBox,
+    OxyDownBox,
+    OxyUpFrame,
+    OxyDownFrame,
+    OxyThinUpBox,
+    OxyThinDownBox,
+    OxyThinUpFrame,
+    OxyThinDownFrame,
+    OxyRoundUpBox,
+    OxyRoundDownBox,
+    OxyButtonUpBox,
+    OxyButtonDownBox,
+    /// A box type FLTK doesn't define, kept as written
+    Other(String),
+}
+
+/// Variant, name written by FLUID, `Fl_Boxtype` value and fltk-rs `FrameType` variant
+const BOX_TYPES: &[(BoxType, &str, i32, &str)] = &[
+    (BoxType::NoBox, "NO_BOX", 0, "NoBox"),
+    (BoxType::FlatBox, "FLAT_BOX", 1, "FlatBox"),
+    (BoxType::UpBox, "UP_BOX", 2, "UpBox"),
+    (BoxType::DownBox, "DOWN_BOX", 3, "DownBox"),
+    (BoxType::UpFrame, "UP_FRAME", 4, "UpFrame"),
+    (BoxType::DownFrame, "DOWN_FRAME", 5, "DownFrame"),
+    (BoxType::ThinUpBox, "THIN_UP_BOX", 6, "ThinUpBox"),
+    (BoxType::ThinDownBox, "THIN_DOWN_BOX", 7, "ThinDownBox"),
+    (BoxType::ThinUpFrame, "THIN_UP_FRAME", 8, "ThinUpFrame"),
+    (BoxType::ThinDownFrame, "THIN_DOWN_FRAME", 9, "ThinDownFrame"),
+    (BoxType::EngravedBox, "ENGRAVED_BOX", 10, "EngravedBox"),
+    (BoxType::EmbossedBox, "EMBOSSED_BOX", 11, "EmbossedBox"),
+    (BoxType::EngravedFrame, "ENGRAVED_FRAME", 12, "EngravedFrame"),
+    (BoxType::EmbossedFrame, "EMBOSSED_FRAME", 13, "EmbossedFrame"),
+    (BoxType::BorderBox, "BORDER_BOX", 14, "BorderBox"),
+    (BoxType::ShadowBox, "SHADOW_BOX", 15, "ShadowBox"),
+    (BoxType::BorderFrame, "BORDER_FRAME", 16, "BorderFrame"),
+    (BoxType::ShadowFrame, "SHADOW_FRAME", 17, "ShadowFrame"),
+    (BoxType::RoundedBox, "ROUNDED_BOX", 18, "RoundedBox"),
+    (BoxType::RShadowBox, "RSHADOW_BOX", 19, "RShadowBox"),
+    (BoxType::RoundedFrame, "ROUNDED_FRAME", 20, "RoundedFrame"),
+    (BoxType::RFlatBox, "RFLAT_BOX", 21, "RFlatBox"),
+    (BoxType::RoundUpBox, "ROUND_UP_BOX", 22, "RoundUpBox"),
+    (BoxType::RoundDownBox, "ROUND_DOWN_BOX", 23, "RoundDownBox"),
+    (BoxType::DiamondUpBox, "DIAMOND_UP_BOX", 24, "DiamondUpBox"),
+    (BoxType::DiamondDownBox, "DIAMOND_DOWN_BOX", 25, "DiamondDownBox"),
+    (BoxType::OvalBox, "OVAL_BOX", 26, "OvalBox"),
+    (BoxType::OShadowBox, "OSHADOW_BOX", 27, "OShadowBox"),
+    (BoxType::OvalFrame, "OVAL_FRAME", 28, "OvalFrame"),
+    (BoxType::OFlatBox, "OFLAT_BOX", 29, "OFlatFrame"),
+    (BoxType::PlasticUpBox, "PLASTIC_UP_BOX", 30, "PlasticUpBox"),
+    (BoxType::PlasticDownBox, "PLASTIC_DOWN_BOX", 31, "PlasticDownBox"),
+    (BoxType::PlasticUpFrame, "PLASTIC_UP_FRAME", 32, "PlasticUpFrame"),
+    (BoxType::PlasticDownFrame, "PLASTIC_DOWN_FRAME", 33, "PlasticDownFrame"),
+    (BoxType::PlasticThinUpBox, "PLASTIC_THIN_UP_BOX", 34, "PlasticThinUpBox"),
+    (BoxType::PlasticThinDownBox, "PLASTIC_THIN_DOWN_BOX", 35, "PlasticThinDownBox"),
+    (BoxType::PlasticRoundUpBox, "PLASTIC_ROUND_UP_BOX", 36, "PlasticRoundUpBox"),
+    (BoxType::PlasticRoundDownBox, "PLASTIC_ROUND_DOWN_BOX", 37, "PlasticRoundDownBox"),
+    (BoxType::GtkUpBox, "GTK_UP_BOX", 38, "GtkUpBox"),
+    (BoxType::GtkDownBox, "GTK_DOWN_BOX", 39, "GtkDownBox"),
+    (BoxType::GtkUpFrame, "GTK_UP_FRAME", 40, "GtkUpFrame"),
+    (BoxType::GtkDownFrame, "GTK_DOWN_FRAME", 41, "GtkDownFrame"),
+    (BoxType::GtkThinUpBox, "GTK_THIN_UP_BOX", 42, "GtkThinUpBox"),
+    (BoxType::GtkThinDownBox, "GTK_THIN_DOWN_BOX", 43, "GtkThinDownBox"),
+    (BoxType::GtkThinUpFrame, "GTK_THIN_UP_FRAME", 44, "GtkThinUpFrame"),
+    (BoxType::GtkThinDownFrame, "GTK_THIN_DOWN_FRAME", 45, "GtkThinDownFrame"),
+    (BoxType::GtkRoundUpBox, "GTK_ROUND_UP_BOX", 46, "GtkRoundUpBox"),
+    (BoxType::GtkRoundDownBox, "GTK_ROUND_DOWN_BOX", 47, "GtkRoundDownBox"),
+    (BoxType::GleamUpBox, "GLEAM_UP_BOX", 48, "GleamUpBox"),
+    (BoxType::GleamDownBox, "GLEAM_DOWN_BOX", 49, "GleamDownBox"),
+    (BoxType::GleamUpFrame, "GLEAM_UP_FRAME", 50, "GleamUpFrame"),
+    (BoxType::GleamDownFrame, "GLEAM_DOWN_FRAME", 51, "GleamDownFrame"),
+    (BoxType::GleamThinUpBox, "GLEAM_THIN_UP_BOX", 52, "GleamThinUpBox"),
+    (BoxType::GleamThinDownBox, "GLEAM_THIN_DOWN_BOX", 53, "GleamThinDownBox"),
+    (BoxType::GleamRoundUpBox, "GLEAM_ROUND_UP_BOX", 54, "GleamRoundUpBox"),
+    (BoxType::GleamRoundDownBox, "GLEAM_ROUND_DOWN_BOX", 55, "GleamRoundDownBox"),
+    (BoxType::OxyUpBox, "OXY_UP_BOX", 56, "OxyUpBox"),
+    (BoxType::OxyDownBox, "OXY_DOWN_BOX", 57, "OxyDownBox"),
+    (BoxType::OxyUpFrame, "OXY_UP_FRAME", 58, "OxyUpFrame"),
+    (BoxType::OxyDownFrame, "OXY_DOWN_FRAME", 59, "OxyDownFrame"),
+    (BoxType::OxyThinUpBox, "OXY_THIN_UP_BOX", 60, "OxyThinUpBox"),
+    (BoxType::OxyThinDownBox, "OXY_THIN_DOWN_BOX", 61, "OxyThinDownBox"),
+    (BoxType::OxyThinUpFrame, "OXY_THIN_UP_FRAME", 62, "OxyThinUpFrame"),
+    (BoxType::OxyThinDownFrame, "OXY_THIN_DOWN_FRAME", 63, "OxyThinDownFrame"),
+    (BoxType::OxyRoundUpBox, "OXY_ROUND_UP_BOX", 64, "OxyRoundUpBox"),
+    (BoxType::OxyRoundDownBox, "OXY_ROUND_DOWN_BOX", 65, "OxyRoundDownBox"),
+    (BoxType::OxyButtonUpBox, "OXY_BUTTON_UP_BOX", 66, "OxyButtonUpBox"),
+    (BoxType::OxyButtonDownBox, "OXY_BUTTON_DOWN_BOX", 67, "OxyButtonDownBox"),
+];
+
+impl BoxType {
+    /// Name written by FLUID, like `FLAT_BOX`
+    pub fn name(&self) -> &str {
+        match self {
+            BoxType::Other(s) => s,
+            b => lookup(BOX_TYPES, b).map_or("", |e| e.1),
+        }
+    }
+
+    /// Value of the `Fl_Boxtype` constant
+    pub fn to_fltk(&self) -> Option<i32> {
+        lookup(BOX_TYPES, self).map(|e| e.2)
+    }
+
+    pub fn from_fltk(v: i32) -> BoxType {
+        match BOX_TYPES.iter().find(|e| e.2 == v) {
+            Some(e) => e.0.clone(),
+            None => BoxType::Other(v.to_string()),
+        }
+    }
+
+    /// Path of the matching fltk-rs enum value, like `fltk::enums::FrameType::FlatBox`
+    pub fn fltk_rs_path(&self) -> Option<String> {
+        lookup(BOX_TYPES, self).map(|e| format!("fltk::enums::FrameType::{}", e.3))
+    }
+}
+
+/// Accepts names with or without the `FL_` prefix, and numeric values
+impl FromStr for BoxType {
+    type Err = ValueError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = strip_fl_prefix(s);
+        if let Some(e) = BOX_TYPES.iter().find(|e| e.1 == name) {
+            return Ok(e.0.clone());
+        }
+        match s.parse() {
+            Ok(v) => Ok(BoxType::from_fltk(v)),
+            Err(_) => Ok(BoxType::Other(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for BoxType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Label types in the order of FLTK's `Fl_Labeltype`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum LabelType {
+    Normal,
+    NoLabel,
+    Shadow,
+    Engraved,
+    Embossed,
+    Multi,
+    Icon,
+    Image,
+    /// A label type FLTK doesn't define, kept as written
+    Other(String),
+}
+
+/// Variant, name written by FLUID, `Fl_Labeltype` value and fltk-rs `LabelType` variant
+const LABEL_TYPES: &[(LabelType, &str, i32, &str)] = &[
+    (LabelType::Normal, "NORMAL_LABEL", 0, "Normal"),
+    (LabelType::NoLabel, "NO_LABEL", 1, "None"),
+    (LabelType::Shadow, "SHADOW_LABEL", 2, "Shadow"),
+    (LabelType::Engraved, "ENGRAVED_LABEL", 3, "Engraved"),
+    (LabelType::Embossed, "EMBOSSED_LABEL", 4, "Embossed"),
+    (LabelType::Multi, "MULTI_LABEL", 5, "Multi"),
+    (LabelType::Icon, "ICON_LABEL", 6, "Icon"),
+    (LabelType::Image, "IMAGE_LABEL", 7, "Image"),
+];
+
+impl LabelType {
+    /// Name written by FLUID, like `NORMAL_LABEL`
+    pub fn name(&self) -> &str {
+        match self {
+            LabelType::Other(s) => s,
+            l => lookup(LABEL_TYPES, l).map_or("", |e| e.1),
+        }
+    }
+
+    /// Value of the `Fl_Labeltype` constant
+    pub fn to_fltk(&self) -> Option<i32> {
+        lookup(LABEL_TYPES, self).map(|e| e.2)
+    }
+
+    pub fn from_fltk(v: i32) -> LabelType {
+        match LABEL_TYPES.iter().find(|e| e.2 == v) {
+            Some(e) => e.0.clone(),
+            None => LabelType::Other(v.to_string()),
+        }
+    }
+
+    /// Path of the matching fltk-rs enum value, like `fltk::enums::LabelType::Normal`
+    pub fn fltk_rs_path(&self) -> Option<String> {
+        lookup(LABEL_TYPES, self).map(|e| format!("fltk::enums::LabelType::{}", e.3))
+    }
+}
+
+/// Accepts names with or without the `FL_` prefix, and numeric values
+impl FromStr for LabelType {
+    type Err = ValueError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = strip_fl_prefix(s);
+        if let Some(e) = LABEL_TYPES.iter().find(|e| e.1 == name) {
+            return Ok(e.0.clone());
+        }
+        match s.parse() {
+            Ok(v) => Ok(LabelType::from_fltk(v)),
+            Err(_) => Ok(LabelType::Other(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for LabelType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+fn lookup<'a, T: PartialEq>(
+    table: &'a [(T, &'static str, i32, &'static str)],
+    v: &T,
+) -> Option<&'a (T, &'static str, i32, &'static str)> {
+    table.iter().find(|e| e.0 == *v)
+}
+
+/// `FL_FLAT_BOX` and FLTK's internal `_FL_ROUNDED_BOX` spellings to FLUID's names
+fn strip_fl_prefix(s: &str) -> &str {
+    s.strip_prefix("_FL_")
+        .or_else(|| s.strip_prefix("FL_"))
+        .unwrap_or(s)
+}
+
+/// An FLTK font index. The first 16 are the built-in faces, each family
+/// being followed by its bold, italic and bold italic variants.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Font(pub i32);
+
+const FONT_NAMES: [&str; 16] = [
+    "FL_HELVETICA",
+    "FL_HELVETICA_BOLD",
+    "FL_HELVETICA_ITALIC",
+    "FL_HELVETICA_BOLD_ITALIC",
+    "FL_COURIER",
+    "FL_COURIER_BOLD",
+    "FL_COURIER_ITALIC",
+    "FL_COURIER_BOLD_ITALIC",
+    "FL_TIMES",
+    "FL_TIMES_BOLD",
+    "FL_TIMES_ITALIC",
+    "FL_TIMES_BOLD_ITALIC",
+    "FL_SYMBOL",
+    "FL_SCREEN",
+    "FL_SCREEN_BOLD",
+    "FL_ZAPF_DINGBATS",
+];
+
+impl Font {
+    pub const HELVETICA: Font = Font(0);
+    pub const HELVETICA_BOLD: Font = Font(1);
+    pub const HELVETICA_ITALIC: Font = Font(2);
+    pub const HELVETICA_BOLD_ITALIC: Font = Font(3);
+    pub const COURIER: Font = Font(4);
+    pub const COURIER_BOLD: Font = Font(5);
+    pub const COURIER_ITALIC: Font = Font(6);
+    pub const COURIER_BOLD_ITALIC: Font = Font(7);
+    pub const TIMES: Font = Font(8);
+    pub const TIMES_BOLD: Font = Font(9);
+    pub const TIMES_ITALIC: Font = Font(10);
+    pub const TIMES_BOLD_ITALIC: Font = Font(11);
+    pub const SYMBOL: Font = Font(12);
+    pub const SCREEN: Font = Font(13);
+    pub const SCREEN_BOLD: Font = Font(14);
+    pub const ZAPF_DINGBATS: Font = Font(15);
+
+    pub fn is_builtin(self) -> bool {
+        (0..16).contains(&self.0)
+    }
+
+    /// Name of the `Fl_Font` constant for built-in faces, like `FL_TIMES_BOLD`
+    pub fn name(self) -> Option<&'static str> {
+        if self.is_builtin() {
+            Some(FONT_NAMES[self.0 as usize])
+        } else {
+            None
+        }
+    }
+
+    /// The regular face of the font's family
+    pub fn family(self) -> Font {
+        match self.0 {
+            0..=11 => Font(self.0 & !3),
+            14 => Font::SCREEN,
+            _ => self,
+        }
+    }
+
+    pub fn is_bold(self) -> bool {
+        matches!(self.0, 0..=11 if self.0 & 1 != 0) || self.0 == 14
+    }
+
+    pub fn is_italic(self) -> bool {
+        matches!(self.0, 0..=11 if self.0 & 2 != 0)
+    }
+}
+
+/// Accepts constant names with or without the `FL_` prefix, and font indices
+impl FromStr for Font {
+    type Err = ValueError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = strip_fl_prefix(s);
+        if let Some(i) = FONT_NAMES.iter().position(|n| &n[3..] == name) {
+            return Ok(Font(i as i32));
+        }
+        s.parse()
+            .map(Font)
+            .map_err(|_| ValueError::new(format!("Invalid font `{}`", s)))
+    }
+}
+
+impl fmt::Display for Font {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.name() {
+            Some(name) => f.write_str(name),
+            None => write!(f, "{}", self.0),
+        }
+    }
+}
+
+/// Label alignment flags, FLTK's `Fl_Align`
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Align(pub i32);
+
+impl Align {
+    pub const CENTER: Align = Align(0);
+    pub const TOP: Align = Align(1);
+    pub const BOTTOM: Align = Align(2);
+    pub const LEFT: Align = Align(4);
+    pub const RIGHT: Align = Align(8);
+    pub const INSIDE: Align = Align(16);
+    pub const TEXT_OVER_IMAGE: Align = Align(0x20);
+    pub const IMAGE_OVER_TEXT: Align = Align(0);
+    pub const CLIP: Align = Align(0x40);
+    pub const WRAP: Align = Align(0x80);
+    pub const IMAGE_NEXT_TO_TEXT: Align = Align(0x100);
+    pub const TEXT_NEXT_TO_IMAGE: Align = Align(0x120);
+    pub const IMAGE_BACKDROP: Align = Align(0x200);
+    /// Bits selecting the position relative to the widget
+    pub const POSITION_MASK: Align = Align(0x0f);
+    /// Bits selecting the position of the image relative to the text
+    pub const IMAGE_MASK: Align = Align(0x320);
+
+    pub fn contains(self, other: Align) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub fn position(self) -> Align {
+        Align(self.0 & Self::POSITION_MASK.0)
+    }
+
+    pub fn image_position(self) -> Align {
+        Align(self.0 & Self::IMAGE_MASK.0)
+    }
+}
+
+impl std::ops::BitOr for Align {
+    type Output = Align;
+    fn bitor(self, rhs: Align) -> Align {
+        Align(self.0 | rhs.0)
+    }
+}
+
+/// The image positions are values of the image bits rather than separate flags
+const ALIGN_NAMES: &[(&str, i32)] = &[
+    ("FL_ALIGN_TOP", 1),
+    ("FL_ALIGN_BOTTOM", 2),
+    ("FL_ALIGN_LEFT", 4),
+    ("FL_ALIGN_RIGHT", 8),
+    ("FL_ALIGN_INSIDE", 16),
+    ("FL_ALIGN_CLIP", 0x40),
+    ("FL_ALIGN_WRAP", 0x80),
+    ("FL_ALIGN_TEXT_NEXT_TO_IMAGE", 0x120),
+    ("FL_ALIGN_TEXT_OVER_IMAGE", 0x20),
+    ("FL_ALIGN_IMAGE_NEXT_TO_TEXT", 0x100),
+    ("FL_ALIGN_IMAGE_BACKDROP", 0x200),
+];
+
+/// Accepts a number or constant names joined by `|`, like `FL_ALIGN_TOP | FL_ALIGN_INSIDE`
+impl FromStr for Align {
+    type Err = ValueError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_flags(s, ALIGN_NAMES, &["FL_ALIGN_CENTER", "FL_ALIGN_IMAGE_OVER_TEXT"]).map(Align)
+    }
+}
+
+impl fmt::Display for Align {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let image = self.image_position().0;
+        let flags: Vec<_> = ALIGN_NAMES
+            .iter()
+            .filter(|(_, v)| match v & Self::IMAGE_MASK.0 {
+                0 => self.0 & v != 0,
+                bits => bits == image,
+            })
+            .collect();
+        let mut names: Vec<String> = flags.iter().map(|(n, _)| n.to_string()).collect();
+        // bits without a name, including image positions FLTK doesn't define
+        let rest = self.0 & !flags.iter().fold(0, |acc, (_, v)| acc | v);
+        if rest != 0 {
+            names.push(format!("{:#x}", rest));
+        }
+        write_flags(f, &names, "FL_ALIGN_CENTER")
+    }
+}
+
+/// When a widget's callback is done, FLTK's `Fl_When`
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct When(pub i32);
+
+impl When {
+    pub const NEVER: When = When(0);
+    pub const CHANGED: When = When(1);
+    pub const NOT_CHANGED: When = When(2);
+    pub const RELEASE: When = When(4);
+    pub const RELEASE_ALWAYS: When = When(6);
+    pub const ENTER_KEY: When = When(8);
+    pub const ENTER_KEY_ALWAYS: When = When(10);
+    pub const ENTER_KEY_CHANGED: When = When(11);
+    pub const CLOSED: When = When(16);
+
+    pub fn contains(self, other: When) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl std::ops::BitOr for When {
+    type Output = When;
+    fn bitor(self, rhs: When) -> When {
+        When(self.0 | rhs.0)
+    }
+}
+
+/// Combined flags come first so they are preferred when displaying
+const WHEN_NAMES: &[(&str, i32)] = &[
+    ("FL_WHEN_ENTER_KEY_CHANGED", 11),
+    ("FL_WHEN_ENTER_KEY_ALWAYS", 10),
+    ("FL_WHEN_RELEASE_ALWAYS", 6),
+    ("FL_WHEN_CLOSED", 16),
+    ("FL_WHEN_ENTER_KEY", 8),
+    ("FL_WHEN_RELEASE", 4),
+    ("FL_WHEN_NOT_CHANGED", 2),
+    ("FL_WHEN_CHANGED", 1),
+];
+
+/// Accepts a number or constant names joined by `|`, like `FL_WHEN_RELEASE | FL_WHEN_CLOSED`
+impl FromStr for When {
+    type Err = ValueError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_flags(s, WHEN_NAMES, &["FL_WHEN_NEVER"]).map(When)
+    }
+}
+
+impl fmt::Display for When {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut names = vec![];
+        let mut rest = self.0;
+        for (n, v) in WHEN_NAMES {
+            if rest & v == *v {
+                names.push(n.to_string());
+                rest &= !v;
+            }
+        }
+        if rest != 0 {
+            names.push(format!("{:#x}", rest));
+        }
+        write_flags(f, &names, "FL_WHEN_NEVER")
+    }
+}
+
+fn parse_flags(s: &str, names: &[(&str, i32)], zero: &[&str]) -> Result<i32, ValueError> {
+    let mut v = 0;
+    for part in s.split('|').map(str::trim) {
+        v |= if let Some(hex) = part.strip_prefix("0x") {
+            i32::from_str_radix(hex, 16).ok()
+        } else if zero.contains(&part) {
+            Some(0)
+        } else {
+            names
+                .iter()
+                .find(|(n, _)| *n == part)
+                .map(|(_, v)| *v)
+                .or_else(|| part.parse().ok())
+        }
+        .ok_or_else(|| ValueError::new(format!("Invalid flag `{}` in `{}`", part, s)))?;
+    }
+    Ok(v)
+}
+
+fn write_flags(f: &mut fmt::Formatter, names: &[String], zero: &str) -> fmt::Result {
+    if names.is_empty() {
+        f.write_str(zero)
+    } else {
+        f.write_str(&names.join(" | "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::WidgetProps;
+    use crate::parser::parse_ok;
+
+    fn style_fixture() -> Vec<WidgetProps> {
+        let a = parse_ok(include_str!("../fl_tests/style.fl"));
+        let w = a.functions().next().unwrap().widgets().next().unwrap();
+        let mut props = vec![w.props.clone()];
+        props.extend(w.children.iter().map(|c| c.props.clone()));
+        props
+    }
+
+    #[test]
+    fn box_types_in_files() {
+        let p = style_fixture();
+        let boxes: Vec<_> = p.iter().map(|p| p.r#box.clone().unwrap()).collect();
+        assert_eq!(
+            boxes,
+            [
+                BoxType::PlasticUpBox,
+                BoxType::RoundUpBox,
+                BoxType::OFlatBox,
+                BoxType::GtkDownBox,
+                BoxType::Other("68".to_string()),
+            ]
+        );
+        assert_eq!(p[1].down_box, Some(BoxType::RoundDownBox));
+        assert_eq!(p[0].down_box, None);
+        assert_eq!(boxes[1].to_fltk(), Some(22));
+        assert_eq!(boxes[3].to_string(), "GTK_DOWN_BOX");
+        assert_eq!(
+            boxes[2].fltk_rs_path().as_deref(),
+            Some("fltk::enums::FrameType::OFlatFrame")
+        );
+        assert_eq!(boxes[4].to_fltk(), None);
+        assert_eq!(boxes[4].fltk_rs_path(), None);
+    }
+
+    #[test]
+    fn label_types_in_files() {
+        let p = style_fixture();
+        let labels: Vec<_> = p.iter().map(|p| p.labeltype.clone()).collect();
+        assert_eq!(
+            labels,
+            [
+                None,
+                Some(LabelType::Engraved),
+                Some(LabelType::NoLabel),
+                None,
+                Some(LabelType::Other("9".to_string())),
+            ]
+        );
+        assert_eq!(LabelType::NoLabel.to_fltk(), Some(1));
+        assert_eq!(
+            LabelType::NoLabel.fltk_rs_path().as_deref(),
+            Some("fltk::enums::LabelType::None")
+        );
+    }
+
+    #[test]
+    fn style_tables_round_trip() {
+        for (i, (b, name, v, _)) in BOX_TYPES.iter().enumerate() {
+            assert_eq!(*v, i as i32);
+            assert_eq!(BoxType::from_fltk(*v), *b);
+            assert_eq!(name.parse::<BoxType>().unwrap(), *b);
+            assert_eq!(format!("FL_{}", name).parse::<BoxType>().unwrap(), *b);
+            assert_eq!(b.name(), *name);
+        }
+        for (i, (l, name, v, _)) in LABEL_TYPES.iter().enumerate() {
+            assert_eq!(*v, i as i32);
+            assert_eq!(LabelType::from_fltk(*v), *l);
+            assert_eq!(name.parse::<LabelType>().unwrap(), *l);
+            assert_eq!(l.name(), *name);
+        }
+        assert_eq!("_FL_ROUNDED_BOX".parse(), Ok(BoxType::RoundedBox));
+        assert_eq!("3".parse(), Ok(BoxType::DownBox));
+        assert_eq!("MY_BOX".parse(), Ok(BoxType::Other("MY_BOX".to_string())));
+        assert_eq!(BoxType::Other("MY_BOX".to_string()).to_string(), "MY_BOX");
+    }
+
+    #[test]
+    fn fonts_in_files() {
+        let p = style_fixture();
+        let fonts: Vec<_> = p.iter().map(|p| p.labelfont).collect();
+        assert_eq!(
+            fonts,
+            [
+                Some(Font::COURIER_BOLD),
+                Some(Font::TIMES_BOLD),
+                Some(Font::SCREEN_BOLD),
+                None,
+                None
+            ]
+        );
+        assert_eq!(p[3].textfont, Some(Font::COURIER));
+        let f = Font::SCREEN_BOLD;
+        assert_eq!(
+            (f.family(), f.is_bold(), f.is_italic()),
+            (Font::SCREEN, true, false)
+        );
+        let f = Font::TIMES_BOLD_ITALIC;
+        assert_eq!(
+            (f.family(), f.is_bold(), f.is_italic()),
+            (Font::TIMES, true, true)
+        );
+        assert_eq!(Font::TIMES_BOLD.to_string(), "FL_TIMES_BOLD");
+        assert_eq!(Font(16).to_string(), "16");
+        assert!(!Font(16).is_builtin());
+        assert_eq!("COURIER_ITALIC".parse(), Ok(Font::COURIER_ITALIC));
+        assert_eq!("FL_SYMBOL".parse(), Ok(Font::SYMBOL));
+        assert_eq!(
+            "Arial".parse::<Font>().unwrap_err().message,
+            "Invalid font `Arial`"
+        );
+    }
+
+    #[test]
+    fn align_in_files() {
+        let p = style_fixture();
+        let align: Vec<_> = p.iter().map(|p| p.align).collect();
+        assert_eq!(
+            align,
+            [
+                Some(Align(80)),
+                None,
+                Some(Align(21)),
+                None,
+                Some(Align(544))
+            ]
+        );
+        let a = Align(80);
+        assert_eq!(a, Align::INSIDE | Align::CLIP);
+        assert_eq!(a.position(), Align::CENTER);
+        assert_eq!(a.to_string(), "FL_ALIGN_INSIDE | FL_ALIGN_CLIP");
+        let a = Align(21);
+        assert!(a.contains(Align::TOP | Align::LEFT));
+        assert!(!a.contains(Align::BOTTOM));
+        assert_eq!(
+            a.to_string(),
+            "FL_ALIGN_TOP | FL_ALIGN_LEFT | FL_ALIGN_INSIDE"
+        );
+        // an image position FLTK doesn't define is kept as a number
+        assert_eq!(Align(544).image_position(), Align(0x220));
+        assert_eq!(Align(544).to_string(), "0x220");
+        assert_eq!(
+            Align::TEXT_NEXT_TO_IMAGE.to_string(),
+            "FL_ALIGN_TEXT_NEXT_TO_IMAGE"
+        );
+        assert_eq!(Align::CENTER.to_string(), "FL_ALIGN_CENTER");
+        assert_eq!(Align(0x1000 | 4).to_string(), "FL_ALIGN_LEFT | 0x1000");
+    }
+
+    #[test]
+    fn parse_align() {
+        for a in [
+            Align(0),
+            Align(80),
+            Align(21),
+            Align(0x120),
+            Align(0x248),
+            Align(544),
+        ] {
+            assert_eq!(a.to_string().parse(), Ok(a));
+        }
+        assert_eq!("FL_ALIGN_TOP|FL_ALIGN_INSIDE".parse(), Ok(Align(17)));
+        assert_eq!("FL_ALIGN_CENTER".parse(), Ok(Align::CENTER));
+        assert_eq!(
+            "FL_ALIGN_TOP | FL_ALIGN_MIDDLE"
+                .parse::<Align>()
+                .unwrap_err()
+                .message,
+            "Invalid flag `FL_ALIGN_MIDDLE` in `FL_ALIGN_TOP | FL_ALIGN_MIDDLE`"
+        );
+    }
+
+    #[test]
+    fn when_in_files() {
+        let p = style_fixture();
+        let when: Vec<_> = p.iter().map(|p| p.when).collect();
+        assert_eq!(
+            when,
+            [
+                None,
+                Some(When::RELEASE_ALWAYS),
+                None,
+                Some(When::ENTER_KEY_CHANGED),
+                None
+            ]
+        );
+        assert!(When::RELEASE_ALWAYS.contains(When::RELEASE));
+        assert!(!When::RELEASE_ALWAYS.contains(When::CHANGED));
+        assert_eq!(When(6).to_string(), "FL_WHEN_RELEASE_ALWAYS");
+        assert_eq!(When(11).to_string(), "FL_WHEN_ENTER_KEY_CHANGED");
+        assert_eq!(When(20).to_string(), "FL_WHEN_CLOSED | FL_WHEN_RELEASE");
+        assert_eq!(When(0).to_string(), "FL_WHEN_NEVER");
+        assert_eq!(When(0x21).to_string(), "FL_WHEN_CHANGED | 0x20");
+        for w in [When(0), When(1), When(6), When(11), When(20), When(0x21)] {
+            assert_eq!(w.to_string().parse(), Ok(w));
+        }
+        assert!("FL_WHEN_SOMETIMES".parse::<When>().is_err());
+    }
+}
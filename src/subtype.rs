@@ -0,0 +1,421 @@
+use crate::ast::Widget;
+use crate::error::ValueError;
+use crate::menu::{FL_MENU_RADIO, FL_MENU_TOGGLE};
+
+/// Meaning of a widget's `type` property
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Subtype {
+    WindowSingle,
+    WindowDouble,
+    /// Fl_Flex, Fl_Pack, Fl_Roller and Fl_Scrollbar orientation
+    Vertical,
+    Horizontal,
+    ScrollHorizontal,
+    ScrollVertical,
+    ScrollBoth,
+    ScrollHorizontalAlways,
+    ScrollVerticalAlways,
+    ScrollBothAlways,
+    SliderVertical,
+    SliderHorizontal,
+    SliderVertFill,
+    SliderHorzFill,
+    SliderVertKnob,
+    SliderHorzKnob,
+    ButtonNormal,
+    ButtonToggle,
+    ButtonRadio,
+    InputNormal,
+    InputFloat,
+    InputInt,
+    InputMultiline,
+    InputSecret,
+    OutputNormal,
+    OutputMultiline,
+    DialDot,
+    DialLine,
+    DialFill,
+    CounterNormal,
+    CounterSimple,
+    BrowserNoSelect,
+    BrowserSelect,
+    BrowserHold,
+    BrowserMulti,
+    PopupNone,
+    Popup1,
+    Popup2,
+    Popup3,
+    Popup12,
+    Popup23,
+    Popup13,
+    Popup123,
+    ClockSquare,
+    ClockRound,
+    SpinnerInt,
+    SpinnerFloat,
+    MenuItemNormal,
+    MenuItemToggle,
+    MenuItemRadio,
+}
+
+/// Groups of widgets sharing the same `type` values
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Family {
+    Window,
+    Flex,
+    Pack,
+    Scroll,
+    Slider,
+    Scrollbar,
+    Button,
+    Input,
+    Output,
+    Dial,
+    Counter,
+    Roller,
+    Browser,
+    MenuButton,
+    Clock,
+    Spinner,
+    MenuItem,
+}
+
+const FAMILIES: &[(Family, &[&str])] = &[
+    (
+        Family::Window,
+        &["Fl_Window", "Fl_Double_Window", "Fl_Overlay_Window"],
+    ),
+    (Family::Flex, &["Fl_Flex"]),
+    (Family::Pack, &["Fl_Pack"]),
+    (Family::Scroll, &["Fl_Scroll"]),
+    (
+        Family::Slider,
+        &[
+            "Fl_Slider",
+            "Fl_Nice_Slider",
+            "Fl_Hor_Slider",
+            "Fl_Value_Slider",
+            "Fl_Hor_Value_Slider",
+            "Fl_Fill_Slider",
+        ],
+    ),
+    (Family::Scrollbar, &["Fl_Scrollbar"]),
+    (
+        Family::Button,
+        &[
+            "Fl_Button",
+            "Fl_Return_Button",
+            "Fl_Light_Button",
+            "Fl_Check_Button",
+            "Fl_Repeat_Button",
+            "Fl_Round_Button",
+            "Fl_Toggle_Button",
+            "Fl_Shortcut_Button",
+        ],
+    ),
+    (
+        Family::Input,
+        &[
+            "Fl_Input",
+            "Fl_Float_Input",
+            "Fl_Int_Input",
+            "Fl_Multiline_Input",
+            "Fl_Secret_Input",
+            "Fl_File_Input",
+        ],
+    ),
+    (Family::Output, &["Fl_Output", "Fl_Multiline_Output"]),
+    (Family::Dial, &["Fl_Dial", "Fl_Line_Dial", "Fl_Fill_Dial"]),
+    (Family::Counter, &["Fl_Counter", "Fl_Simple_Counter"]),
+    (Family::Roller, &["Fl_Roller"]),
+    (
+        Family::Browser,
+        &[
+            "Fl_Browser",
+            "Fl_Hold_Browser",
+            "Fl_Multi_Browser",
+            "Fl_Select_Browser",
+            "Fl_File_Browser",
+        ],
+    ),
+    (Family::MenuButton, &["Fl_Menu_Button"]),
+    (Family::Clock, &["Fl_Clock"]),
+    (Family::Spinner, &["Fl_Spinner"]),
+    (
+        Family::MenuItem,
+        &["MenuItem", "Checkbox", "RadioMenuItem"],
+    ),
+];
+
+/// Family, `type` value written by FLUID, subtype, FLTK value and FLTK constant
+const SUBTYPES: &[(Family, &str, Subtype, i32, &str)] = &[
+    (Family::Window, "Single", Subtype::WindowSingle, 0xf0, "FL_WINDOW"),
+    (Family::Window, "Double", Subtype::WindowDouble, 0xf1, "FL_DOUBLE_WINDOW"),
+    (Family::Flex, "VERTICAL", Subtype::Vertical, 0, "Fl_Flex::VERTICAL"),
+    (Family::Flex, "COLUMN", Subtype::Vertical, 0, "Fl_Flex::VERTICAL"),
+    (Family::Flex, "HORIZONTAL", Subtype::Horizontal, 1, "Fl_Flex::HORIZONTAL"),
+    (Family::Flex, "ROW", Subtype::Horizontal, 1, "Fl_Flex::HORIZONTAL"),
+    (Family::Pack, "VERTICAL", Subtype::Vertical, 0, "Fl_Pack::VERTICAL"),
+    (Family::Pack, "HORIZONTAL", Subtype::Horizontal, 1, "Fl_Pack::HORIZONTAL"),
+    (Family::Scroll, "HORIZONTAL", Subtype::ScrollHorizontal, 1, "Fl_Scroll::HORIZONTAL"),
+    (Family::Scroll, "VERTICAL", Subtype::ScrollVertical, 2, "Fl_Scroll::VERTICAL"),
+    (Family::Scroll, "BOTH", Subtype::ScrollBoth, 3, "Fl_Scroll::BOTH"),
+    (
+        Family::Scroll,
+        "HORIZONTAL_ALWAYS",
+        Subtype::ScrollHorizontalAlways,
+        5,
+        "Fl_Scroll::HORIZONTAL_ALWAYS",
+    ),
+    (
+        Family::Scroll,
+        "VERTICAL_ALWAYS",
+        Subtype::ScrollVerticalAlways,
+        6,
+        "Fl_Scroll::VERTICAL_ALWAYS",
+    ),
+    (
+        Family::Scroll,
+        "BOTH_ALWAYS",
+        Subtype::ScrollBothAlways,
+        7,
+        "Fl_Scroll::BOTH_ALWAYS",
+    ),
+    (Family::Slider, "Vertical", Subtype::SliderVertical, 0, "FL_VERT_SLIDER"),
+    (Family::Slider, "Horizontal", Subtype::SliderHorizontal, 1, "FL_HOR_SLIDER"),
+    (Family::Slider, "Vert Fill", Subtype::SliderVertFill, 2, "FL_VERT_FILL_SLIDER"),
+    (Family::Slider, "Horz Fill", Subtype::SliderHorzFill, 3, "FL_HOR_FILL_SLIDER"),
+    (Family::Slider, "Vert Knob", Subtype::SliderVertKnob, 4, "FL_VERT_NICE_SLIDER"),
+    (Family::Slider, "Horz Knob", Subtype::SliderHorzKnob, 5, "FL_HOR_NICE_SLIDER"),
+    (Family::Scrollbar, "Vertical", Subtype::Vertical, 0, "FL_VERTICAL"),
+    (Family::Scrollbar, "Horizontal", Subtype::Horizontal, 1, "FL_HORIZONTAL"),
+    (Family::Button, "Normal", Subtype::ButtonNormal, 0, "FL_NORMAL_BUTTON"),
+    (Family::Button, "Toggle", Subtype::ButtonToggle, 1, "FL_TOGGLE_BUTTON"),
+    (Family::Button, "Radio", Subtype::ButtonRadio, 102, "FL_RADIO_BUTTON"),
+    (Family::Input, "Normal", Subtype::InputNormal, 0, "FL_NORMAL_INPUT"),
+    (Family::Input, "Float", Subtype::InputFloat, 1, "FL_FLOAT_INPUT"),
+    (Family::Input, "Int", Subtype::InputInt, 2, "FL_INT_INPUT"),
+    (Family::Input, "Multiline", Subtype::InputMultiline, 4, "FL_MULTILINE_INPUT"),
+    (Family::Input, "Secret", Subtype::InputSecret, 5, "FL_SECRET_INPUT"),
+    (Family::Output, "Normal", Subtype::OutputNormal, 8, "FL_NORMAL_OUTPUT"),
+    (Family::Output, "Multiline", Subtype::OutputMultiline, 12, "FL_MULTILINE_OUTPUT"),
+    (Family::Dial, "Dot", Subtype::DialDot, 0, "FL_NORMAL_DIAL"),
+    (Family::Dial, "Line", Subtype::DialLine, 1, "FL_LINE_DIAL"),
+    (Family::Dial, "Fill", Subtype::DialFill, 2, "FL_FILL_DIAL"),
+    (Family::Counter, "Normal", Subtype::CounterNormal, 0, "FL_NORMAL_COUNTER"),
+    (Family::Counter, "Simple", Subtype::CounterSimple, 1, "FL_SIMPLE_COUNTER"),
+    (Family::Roller, "Vertical", Subtype::Vertical, 0, "FL_VERTICAL"),
+    (Family::Roller, "Horizontal", Subtype::Horizontal, 1, "FL_HORIZONTAL"),
+    (Family::Browser, "No Select", Subtype::BrowserNoSelect, 0, "FL_NORMAL_BROWSER"),
+    (Family::Browser, "Select", Subtype::BrowserSelect, 1, "FL_SELECT_BROWSER"),
+    (Family::Browser, "Hold", Subtype::BrowserHold, 2, "FL_HOLD_BROWSER"),
+    (Family::Browser, "Multi", Subtype::BrowserMulti, 3, "FL_MULTI_BROWSER"),
+    (Family::MenuButton, "normal", Subtype::PopupNone, 0, "0"),
+    (Family::MenuButton, "popup1", Subtype::Popup1, 1, "Fl_Menu_Button::POPUP1"),
+    (Family::MenuButton, "popup2", Subtype::Popup2, 2, "Fl_Menu_Button::POPUP2"),
+    (Family::MenuButton, "popup3", Subtype::Popup3, 4, "Fl_Menu_Button::POPUP3"),
+    (Family::MenuButton, "popup12", Subtype::Popup12, 3, "Fl_Menu_Button::POPUP12"),
+    (Family::MenuButton, "popup23", Subtype::Popup23, 6, "Fl_Menu_Button::POPUP23"),
+    (Family::MenuButton, "popup13", Subtype::Popup13, 5, "Fl_Menu_Button::POPUP13"),
+    (Family::MenuButton, "popup123", Subtype::Popup123, 7, "Fl_Menu_Button::POPUP123"),
+    (Family::Clock, "Square", Subtype::ClockSquare, 0, "FL_SQUARE_CLOCK"),
+    (Family::Clock, "Round", Subtype::ClockRound, 1, "FL_ROUND_CLOCK"),
+    (Family::Spinner, "Integer", Subtype::SpinnerInt, 2, "FL_INT_INPUT"),
+    (Family::Spinner, "Float", Subtype::SpinnerFloat, 1, "FL_FLOAT_INPUT"),
+    (Family::MenuItem, "Normal", Subtype::MenuItemNormal, 0, "0"),
+    (Family::MenuItem, "Toggle", Subtype::MenuItemToggle, FL_MENU_TOGGLE, "FL_MENU_TOGGLE"),
+    (Family::MenuItem, "Radio", Subtype::MenuItemRadio, FL_MENU_RADIO, "FL_MENU_RADIO"),
+];
+
+/// A decoded `type` property
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DecodedType {
+    pub subtype: Subtype,
+    /// Value passed to the widget's `type()`
+    pub value: i32,
+    /// FLTK constant with that value, like `FL_HOR_NICE_SLIDER`
+    pub constant: &'static str,
+}
+
+/// Decodes the `type` value `typ` of a widget of type `widget`, which FLUID
+/// writes as the name shown in its type menu or as a number
+pub fn decode_type(widget: &str, typ: &str) -> Result<DecodedType, ValueError> {
+    let family = FAMILIES
+        .iter()
+        .find(|(_, widgets)| widgets.contains(&widget))
+        .map(|(f, _)| *f)
+        .ok_or_else(|| ValueError::new(format!("`{}` has no known types", widget)))?;
+    let value: Option<i32> = typ.parse().ok();
+    SUBTYPES
+        .iter()
+        .filter(|e| e.0 == family)
+        .find(|e| e.1.eq_ignore_ascii_case(typ) || Some(e.3) == value)
+        .map(|e| DecodedType {
+            subtype: e.2,
+            value: e.3,
+            constant: e.4,
+        })
+        .ok_or_else(|| ValueError::new(format!("Unknown type `{}` for `{}`", typ, widget)))
+}
+
+impl Widget {
+    /// Decodes the widget's `type` property, `None` when it has none
+    pub fn subtype(&self) -> Result<Option<DecodedType>, ValueError> {
+        match &self.props.typ {
+            Some(typ) => decode_type(&self.typ, typ).map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Severity;
+    use crate::lexer::Lexer;
+    use crate::parser::{parse_ok, Parser};
+
+    const SUBTYPES_FL: &str = include_str!("../fl_tests/subtypes.fl");
+
+    #[test]
+    fn subtypes_in_files() {
+        let a = parse_ok(SUBTYPES_FL);
+        let win = a.functions().next().unwrap().widgets().next().unwrap();
+        let t = win.subtype().unwrap().unwrap();
+        assert_eq!(
+            (t.subtype, t.value, t.constant),
+            (Subtype::WindowDouble, 0xf1, "FL_DOUBLE_WINDOW")
+        );
+        let types: Vec<_> = win.children[..7]
+            .iter()
+            .map(|w| {
+                let t = w.subtype().unwrap().unwrap();
+                (w.typ.as_str(), t.subtype, t.value, t.constant)
+            })
+            .collect();
+        assert_eq!(
+            types,
+            [
+                ("Fl_Button", Subtype::ButtonToggle, 1, "FL_TOGGLE_BUTTON"),
+                (
+                    "Fl_Round_Button",
+                    Subtype::ButtonRadio,
+                    102,
+                    "FL_RADIO_BUTTON"
+                ),
+                ("Fl_Input", Subtype::InputInt, 2, "FL_INT_INPUT"),
+                (
+                    "Fl_Value_Slider",
+                    Subtype::SliderHorzKnob,
+                    5,
+                    "FL_HOR_NICE_SLIDER"
+                ),
+                (
+                    "Fl_Scroll",
+                    Subtype::ScrollBothAlways,
+                    7,
+                    "Fl_Scroll::BOTH_ALWAYS"
+                ),
+                ("Fl_Browser", Subtype::BrowserMulti, 3, "FL_MULTI_BROWSER"),
+                (
+                    "Fl_Menu_Button",
+                    Subtype::Popup3,
+                    4,
+                    "Fl_Menu_Button::POPUP3"
+                ),
+            ]
+        );
+        assert_eq!(
+            win.children[7].subtype().unwrap_err().message,
+            "Unknown type `Spiral` for `Fl_Dial`"
+        );
+        assert_eq!(win.children[8].subtype(), Ok(None));
+    }
+
+    #[test]
+    fn menu_item_and_flex_subtypes() {
+        let a = parse_ok(include_str!("../fl_tests/menu.fl"));
+        let bar = &a
+            .functions()
+            .next()
+            .unwrap()
+            .widgets()
+            .next()
+            .unwrap()
+            .children[0];
+        let view = &bar.children[1].children;
+        let toolbar = view[0].subtype().unwrap().unwrap();
+        assert_eq!(
+            (toolbar.subtype, toolbar.value),
+            (Subtype::MenuItemToggle, FL_MENU_TOGGLE)
+        );
+        let small = view[2].subtype().unwrap().unwrap();
+        assert_eq!(
+            (small.subtype, small.value),
+            (Subtype::MenuItemRadio, FL_MENU_RADIO)
+        );
+        let a = parse_ok(include_str!("../fl_tests/flex.fl"));
+        let flex = a.classes().next().unwrap().functions().next().unwrap();
+        let win = flex.widgets().next().unwrap();
+        let inner = win.children[0].children[0].subtype().unwrap().unwrap();
+        assert_eq!(
+            (inner.subtype, inner.constant),
+            (Subtype::Horizontal, "Fl_Flex::HORIZONTAL")
+        );
+    }
+
+    #[test]
+    fn decode_values_and_names() {
+        let t = decode_type("Fl_Button", "1").unwrap();
+        assert_eq!(t.subtype, Subtype::ButtonToggle);
+        assert_eq!(
+            decode_type("Fl_Button", "toggle").unwrap().subtype,
+            Subtype::ButtonToggle
+        );
+        assert_eq!(
+            decode_type("Fl_Flex", "ROW").unwrap().subtype,
+            Subtype::Horizontal
+        );
+        assert_eq!(
+            decode_type("Fl_Scrollbar", "1").unwrap().constant,
+            "FL_HORIZONTAL"
+        );
+        assert_eq!(decode_type("Fl_Spinner", "Integer").unwrap().value, 2);
+        assert_eq!(
+            decode_type("Fl_Button", "3").unwrap_err().message,
+            "Unknown type `3` for `Fl_Button`"
+        );
+        assert_eq!(
+            decode_type("Fl_Box", "Normal").unwrap_err().message,
+            "`Fl_Box` has no known types"
+        );
+    }
+
+    #[test]
+    fn unknown_types_are_warnings() {
+        let (a, d) = Parser::new(Lexer::new(SUBTYPES_FL)).parse_with_diagnostics();
+        assert_eq!(a.functions().count(), 1);
+        let d: Vec<_> = d
+            .iter()
+            .map(|d| {
+                (
+                    d.severity,
+                    &SUBTYPES_FL[d.span.start..d.span.end],
+                    &*d.message,
+                )
+            })
+            .collect();
+        assert_eq!(
+            d,
+            [(
+                Severity::Warning,
+                "Spiral",
+                "Unknown type `Spiral` for `Fl_Dial`"
+            )]
+        );
+        // instances of widget classes aren't checked
+        let s = "widget_class Knob {open\n  xywh {0 0 50 50} visible\n} {}\nFunction {} {} {\n  Knob {} {\n    xywh {0 0 50 50} type Spiral\n  }\n}\n";
+        let (_, d) = Parser::new(Lexer::new(s)).parse_with_diagnostics();
+        assert!(d.is_empty());
+    }
+}
@@ -1,5 +1,8 @@
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+use crate::span::Span;
+
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
 pub enum TokenType {
+    #[default]
     Unknown,
     Word,
     OpenBrace,
@@ -7,12 +10,6 @@ pub enum TokenType {
     Eof,
 }
 
-impl Default for TokenType {
-    fn default() -> Self {
-        TokenType::Unknown
-    }
-}
-
 #[derive(Default, Debug, Copy, Clone)]
 pub struct Token<'a> {
     pub typ: TokenType,
@@ -20,3 +17,9 @@ pub struct Token<'a> {
     pub start: usize,
     pub end: usize,
 }
+
+impl<'a> Token<'a> {
+    pub fn span(&self) -> Span {
+        Span::new(self.start, self.end)
+    }
+}
@@ -0,0 +1,192 @@
+use crate::ast::*;
+use std::collections::HashSet;
+
+/// Widget types FLUID knows about, including the menu item types
+pub const BUILTIN_WIDGETS: &[&str] = &[
+    "Fl_Window",
+    "Fl_Double_Window",
+    "Fl_Overlay_Window",
+    "Fl_Group",
+    "Fl_Pack",
+    "Fl_Flex",
+    "Fl_Grid",
+    "Fl_Tabs",
+    "Fl_Scroll",
+    "Fl_Tile",
+    "Fl_Wizard",
+    "Fl_Box",
+    "Fl_Button",
+    "Fl_Return_Button",
+    "Fl_Light_Button",
+    "Fl_Check_Button",
+    "Fl_Repeat_Button",
+    "Fl_Round_Button",
+    "Fl_Toggle_Button",
+    "Fl_Shortcut_Button",
+    "Fl_Input",
+    "Fl_Float_Input",
+    "Fl_Int_Input",
+    "Fl_Multiline_Input",
+    "Fl_Secret_Input",
+    "Fl_File_Input",
+    "Fl_Output",
+    "Fl_Multiline_Output",
+    "Fl_Text_Display",
+    "Fl_Text_Editor",
+    "Fl_Simple_Terminal",
+    "Fl_Terminal",
+    "Fl_Help_View",
+    "Fl_Browser",
+    "Fl_Hold_Browser",
+    "Fl_Multi_Browser",
+    "Fl_Select_Browser",
+    "Fl_File_Browser",
+    "Fl_Check_Browser",
+    "Fl_Tree",
+    "Fl_Table",
+    "Fl_Table_Row",
+    "Fl_Menu_Bar",
+    "Fl_Sys_Menu_Bar",
+    "Fl_Menu_Button",
+    "Fl_Choice",
+    "Fl_Input_Choice",
+    "Fl_Slider",
+    "Fl_Nice_Slider",
+    "Fl_Hor_Slider",
+    "Fl_Value_Slider",
+    "Fl_Hor_Value_Slider",
+    "Fl_Fill_Slider",
+    "Fl_Scrollbar",
+    "Fl_Adjuster",
+    "Fl_Counter",
+    "Fl_Simple_Counter",
+    "Fl_Dial",
+    "Fl_Line_Dial",
+    "Fl_Fill_Dial",
+    "Fl_Roller",
+    "Fl_Spinner",
+    "Fl_Value_Input",
+    "Fl_Value_Output",
+    "Fl_Clock",
+    "Fl_Progress",
+    "Fl_Positioner",
+    "MenuItem",
+    "Submenu",
+    "Checkbox",
+    "RadioMenuItem",
+];
+
+pub fn is_builtin(typ: &str) -> bool {
+    BUILTIN_WIDGETS.contains(&typ)
+}
+
+impl Ast {
+    /// Finds a widget class by name, including those nested in classes and declaration blocks
+    pub fn find_widget_class(&self, name: &str) -> Option<&WidgetClass> {
+        let mut classes = vec![];
+        collect_widget_classes(&self.items, &mut classes);
+        classes.into_iter().find(|w| w.name == name)
+    }
+
+    /// Returns the definition `w` is an instance of
+    pub fn widget_class_of(&self, w: &Widget) -> Option<&WidgetClass> {
+        match w.kind {
+            WidgetKind::WidgetClass => self.find_widget_class(&w.typ),
+            _ => None,
+        }
+    }
+}
+
+fn collect_widget_classes<'a>(items: &'a [Item], out: &mut Vec<&'a WidgetClass>) {
+    for item in items {
+        match item {
+            Item::WidgetClass(w) => out.push(w),
+            Item::Class(c) => collect_widget_classes(&c.members, out),
+            Item::DeclBlock(d) => collect_widget_classes(&d.children, out),
+            _ => (),
+        }
+    }
+}
+
+/// Sets the kind of every widget once all widget classes are known
+pub(crate) fn resolve_kinds(ast: &mut Ast) {
+    let mut classes = vec![];
+    collect_widget_classes(&ast.items, &mut classes);
+    let classes: HashSet<String> = classes.into_iter().map(|w| w.name.clone()).collect();
+    resolve_items(&mut ast.items, &classes);
+}
+
+fn resolve_items(items: &mut [Item], classes: &HashSet<String>) {
+    for item in items {
+        match item {
+            Item::Class(c) => resolve_items(&mut c.members, classes),
+            Item::Function(f) => resolve_items(&mut f.children, classes),
+            Item::CodeBlock(c) => resolve_items(&mut c.children, classes),
+            Item::DeclBlock(d) => resolve_items(&mut d.children, classes),
+            Item::WidgetClass(w) => {
+                for c in &mut w.children {
+                    resolve_widget(c, classes);
+                }
+            }
+            Item::Widget(w) => resolve_widget(w, classes),
+            Item::Decl(_) | Item::Comment(_) | Item::Code(_) | Item::Data(_) => (),
+        }
+    }
+}
+
+fn resolve_widget(w: &mut Widget, classes: &HashSet<String>) {
+    w.kind = if is_builtin(&w.typ) {
+        WidgetKind::Builtin
+    } else if classes.contains(&w.typ) {
+        WidgetKind::WidgetClass
+    } else {
+        WidgetKind::Unknown
+    };
+    for c in &mut w.children {
+        resolve_widget(c, classes);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_ok;
+
+    #[test]
+    fn widget_kinds() {
+        let a = parse_ok(include_str!("../fl_tests/wc_instance.fl"));
+        let win = a.functions().next().unwrap().widgets().next().unwrap();
+        assert_eq!(win.kind, WidgetKind::Builtin);
+        let kinds: Vec<_> = win
+            .children
+            .iter()
+            .map(|w| (w.typ.as_str(), w.kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                ("MyPanel", WidgetKind::WidgetClass),
+                ("Fancy_Slider", WidgetKind::Unknown),
+            ]
+        );
+        assert_eq!(a.widget_class_of(&win.children[0]).unwrap().name, "MyPanel");
+        assert!(a.widget_class_of(&win.children[1]).is_none());
+        assert!(a.widget_class_of(win).is_none());
+        // children of a widget class are resolved too
+        let panel = a.find_widget_class("MyPanel").unwrap();
+        assert_eq!(panel.children[0].kind, WidgetKind::Builtin);
+    }
+
+    #[test]
+    fn widget_classes_nested_in_classes() {
+        let a = parse_ok(include_str!("../fl_tests/wc_instance.fl"));
+        let toolbar = a.find_widget_class("Toolbar").unwrap();
+        assert_eq!(toolbar.props.class.as_deref(), Some("Fl_Pack"));
+        assert_eq!(toolbar.props.position_relative, Some(true));
+        let f = a.classes().next().unwrap().functions().next().unwrap();
+        let bar = &f.widgets().next().unwrap().children[0];
+        assert_eq!(bar.kind, WidgetKind::WidgetClass);
+        assert_eq!(a.widget_class_of(bar).unwrap().name, "Toolbar");
+        assert!(a.find_widget_class("Fancy_Slider").is_none());
+    }
+}
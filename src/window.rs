@@ -0,0 +1,168 @@
+use crate::ast::{Widget, WidgetClass, WidgetProps};
+use crate::error::ValueError;
+use crate::geometry::SizeRange;
+use crate::subtype::{decode_type, Subtype};
+
+/// Window types whose props have a `WindowProps` view
+pub const WINDOW_WIDGETS: &[&str] = &["Fl_Window", "Fl_Double_Window", "Fl_Overlay_Window"];
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum Modality {
+    #[default]
+    Normal,
+    /// Blocks events to the other windows, `modal`
+    Modal,
+    /// Stays above the other windows, `non_modal`
+    NonModal,
+}
+
+/// Window settings resolved from the generic widget props
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct WindowProps {
+    pub modality: Modality,
+    /// Whether the window has a border, unset by `noborder`
+    pub border: bool,
+    /// X class of the window
+    pub xclass: Option<String>,
+    /// `WidgetProps::size_limits`, the parsed `size_range`
+    pub size_range: Option<SizeRange>,
+    pub double_buffered: bool,
+    /// Whether the window is shown under the mouse
+    pub hotspot: bool,
+    pub visible: bool,
+}
+
+impl WindowProps {
+    /// Resolves the props of a window of widget type `typ`
+    pub fn from_props(typ: &str, props: &WidgetProps) -> Result<WindowProps, ValueError> {
+        let modality = match (props.modal, props.non_modal) {
+            (Some(true), Some(true)) => {
+                return Err(ValueError::new(
+                    "Window is set to be both `modal` and `non_modal`",
+                ))
+            }
+            (Some(true), _) => Modality::Modal,
+            (_, Some(true)) => Modality::NonModal,
+            _ => Modality::Normal,
+        };
+        let double_buffered = match &props.typ {
+            Some(t) => decode_type("Fl_Window", t)?.subtype == Subtype::WindowDouble,
+            None => typ == "Fl_Double_Window",
+        };
+        Ok(WindowProps {
+            modality,
+            border: !props.noborder.unwrap_or(false),
+            xclass: props.xclass.clone(),
+            size_range: props.size_limits,
+            double_buffered,
+            hotspot: props.hotspot.unwrap_or(false),
+            visible: props.visible.unwrap_or(false),
+        })
+    }
+}
+
+impl Widget {
+    /// Window settings of a window, `None` for other widgets
+    pub fn window_props(&self) -> Result<Option<WindowProps>, ValueError> {
+        if !WINDOW_WIDGETS.contains(&self.typ.as_str()) {
+            return Ok(None);
+        }
+        WindowProps::from_props(&self.typ, &self.props).map(Some)
+    }
+}
+
+impl WidgetClass {
+    /// Window settings of a class based on a window, `None` for other classes
+    pub fn window_props(&self) -> Result<Option<WindowProps>, ValueError> {
+        let base = self.props.class.as_deref().unwrap_or("Fl_Group");
+        if !WINDOW_WIDGETS.contains(&base) {
+            return Ok(None);
+        }
+        WindowProps::from_props(base, &self.widget_props).map(Some)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::SizeRange;
+    use crate::parser::parse_ok;
+
+    #[test]
+    fn widget_class_window_props() {
+        let a = parse_ok(include_str!("../fl_tests/wc_instance.fl"));
+        // based on Fl_Group and Fl_Pack
+        assert_eq!(a.find_widget_class("MyPanel").unwrap().window_props(), Ok(None));
+        assert_eq!(a.find_widget_class("Toolbar").unwrap().window_props(), Ok(None));
+        let popup = a.find_widget_class("Popup").unwrap();
+        assert_eq!(popup.props.position_relative, Some(true));
+        assert_eq!(popup.props.position_relative_rescale, Some(true));
+        let w = popup.window_props().unwrap().unwrap();
+        assert_eq!(w.modality, Modality::Modal);
+        assert!(w.double_buffered && w.border && w.visible);
+        assert_eq!(
+            w.size_range,
+            Some(SizeRange {
+                min_w: 200,
+                min_h: 100,
+                ..Default::default()
+            })
+        );
+    }
+
+    fn fixture_windows() -> Vec<Widget> {
+        let a = parse_ok(include_str!("../fl_tests/windows.fl"));
+        a.functions()
+            .map(|f| f.widgets().next().unwrap().clone())
+            .collect()
+    }
+
+    #[test]
+    fn window_props() {
+        let w = fixture_windows();
+        assert_eq!(
+            w[0].window_props(),
+            Ok(Some(WindowProps {
+                modality: Modality::Normal,
+                border: true,
+                xclass: Some("MyApp".to_string()),
+                size_range: Some(SizeRange {
+                    min_w: 200,
+                    min_h: 150,
+                    max_w: 800,
+                    max_h: 600,
+                    ..Default::default()
+                }),
+                double_buffered: false,
+                hotspot: true,
+                visible: true,
+            }))
+        );
+        let tools = w[1].window_props().unwrap().unwrap();
+        assert_eq!(tools.modality, Modality::NonModal);
+        assert!(!tools.border && tools.double_buffered && !tools.visible);
+        assert_eq!(tools.size_range, None);
+        // the widget type sets double buffering when there is no `type`
+        let dialog = w[2].window_props().unwrap().unwrap();
+        assert_eq!(dialog.modality, Modality::Modal);
+        assert!(dialog.border && dialog.double_buffered && dialog.visible);
+        assert_eq!(w[0].children[0].window_props(), Ok(None));
+    }
+
+    #[test]
+    fn window_prop_errors() {
+        let w = fixture_windows();
+        assert_eq!(
+            w[3].window_props().unwrap_err().message,
+            "Window is set to be both `modal` and `non_modal`"
+        );
+        let mut props = w[0].props.clone();
+        props.typ = Some("Triple".to_string());
+        assert_eq!(
+            WindowProps::from_props("Fl_Window", &props)
+                .unwrap_err()
+                .message,
+            "Unknown type `Triple` for `Fl_Window`"
+        );
+    }
+}